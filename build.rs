@@ -0,0 +1,40 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Captures the resolved versions of the Solana crates from Cargo.lock for GET /version
+fn main() {
+    let lock_path = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+
+    let lockfile = fs::read_to_string(&lock_path).unwrap_or_default();
+    for (package, env_name) in [
+        ("solana-sdk", "SOLANA_SDK_VERSION"),
+        ("spl-token", "SPL_TOKEN_VERSION"),
+        ("spl-token-2022", "SPL_TOKEN_2022_VERSION"),
+    ] {
+        let version = locked_versions(&lockfile, package);
+        let version = if version.is_empty() { "unknown".to_string() } else { version.join(", ") };
+        println!("cargo:rustc-env={}={}", env_name, version);
+    }
+}
+
+// Cargo.lock lists each package as `name = "..."` directly followed by `version = "..."`
+fn locked_versions(lockfile: &str, package: &str) -> Vec<String> {
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lockfile.lines();
+    let mut versions = Vec::new();
+    while let Some(line) = lines.next() {
+        if line.trim() != name_line {
+            continue;
+        }
+        if let Some(version) = lines
+            .next()
+            .and_then(|line| line.trim().strip_prefix("version = \""))
+            .and_then(|rest| rest.strip_suffix('"'))
+        {
+            versions.push(version.to_string());
+        }
+    }
+    versions
+}
@@ -1,16 +1,43 @@
-use warp::Filter;
-use serde::{Deserialize, Serialize};
+// The /examples json! literal outgrows the default macro recursion limit
+#![recursion_limit = "256"]
+
+use warp::{filters::BoxedFilter, Filter};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::json;
 use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    ed25519_instruction,
+    feature_set::FeatureSet,
+    hash::{hashv, Hash},
+    instruction::{AccountMeta, Instruction},
+    message::{v0, Message, VersionedMessage},
+    native_token::LAMPORTS_PER_SOL,
+    packet::PACKET_DATA_SIZE,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
     pubkey::Pubkey,
-    signature::{Keypair, Signer, Signature},
+    secp256k1_instruction,
+    derivation_path::DerivationPath,
+    signature::{keypair_from_seed, keypair_from_seed_and_derivation_path, Keypair, Signer, Signature},
     system_instruction,
+    sysvar::rent::Rent,
+    transaction::VersionedTransaction,
 };
 use spl_token::instruction as token_instruction;
-use spl_associated_token_account;
+use spl_token_2022::extension::ExtensionType;
 use std::str::FromStr;
 use base64::{Engine as _, engine::general_purpose::STANDARD};
-use bs58;
 use std::env;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use futures::{FutureExt, StreamExt};
+use tokio::sync::Semaphore;
 
 
 #[derive(Serialize)]
@@ -20,12 +47,90 @@ struct ApiResponse<T> {
     data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<Vec<FieldError>>,
+    // The on-chain ProgramError code when an instruction builder rejected the input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct FieldError {
+    field: String,
+    reason: String,
 }
 
 #[derive(Serialize)]
 struct KeypairData {
+    pubkey: String,
+    // Base58 of the full 64-byte keypair, the format Phantom and other wallets import directly
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DerivedKeypairData {
     pubkey: String,
     secret: String,
+    derivation_path: String,
+}
+
+#[derive(Serialize)]
+struct PubkeyData {
+    pubkey: String,
+}
+
+#[derive(Serialize)]
+struct OffCurveData {
+    pubkey: String,
+    attempts: u32,
+}
+
+#[derive(Serialize)]
+struct DerivedAddressData {
+    address: String,
+    owner: String,
+    mint: String,
+    // Echoed so clients can confirm whether the token or token-2022 derivation was applied
+    token_program: String,
+}
+
+#[derive(Serialize)]
+struct NormalizedPubkeyData {
+    pubkey: String,
+    changed: bool,
+}
+
+#[derive(Serialize)]
+struct PubkeyEncodingsData {
+    base58: String,
+    hex: String,
+    bytes: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct ChallengeSignatureData {
+    pubkey: String,
+    signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PairMatchData {
+    matches: bool,
+}
+
+#[derive(Serialize)]
+struct KeypairInspectData {
+    byte_length: usize,
+    is_valid_keypair: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    derived_pubkey: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -33,6 +138,37 @@ struct InstructionData {
     program_id: String,
     accounts: Vec<AccountInfo>,
     instruction_data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ui_amount_string: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MintBatchEntry {
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instruction: Option<InstructionData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MintBatchData {
+    results: Vec<MintBatchEntry>,
+}
+
+#[derive(Serialize)]
+struct CreateTokenData<T> {
+    #[serde(flatten)]
+    instruction: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mint_secret: Option<String>,
+}
+
+#[derive(Serialize)]
+struct InstructionSequenceData {
+    instructions: Vec<InstructionData>,
 }
 
 #[derive(Serialize)]
@@ -45,8 +181,29 @@ struct AccountInfo {
 #[derive(Serialize)]
 struct SignatureData {
     signature: String,
+    // The raw 64 bytes as they appear in a transaction's signatures array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature_bytes: Option<Vec<u8>>,
     public_key: String,
     message: String,
+    message_len: usize,
+}
+
+#[derive(Serialize)]
+struct MultiSignatureEntry {
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MultiSignatureData {
+    message: String,
+    signatures: Vec<MultiSignatureEntry>,
 }
 
 #[derive(Serialize)]
@@ -56,18 +213,128 @@ struct VerifyData {
     pubkey: String,
 }
 
+#[derive(Serialize)]
+struct HealthData {
+    status: String,
+    base_path: String,
+    requests_served: u64,
+    uptime_seconds: u64,
+    derivation_cache_hits: u64,
+    derivation_cache_misses: u64,
+}
+
+#[derive(Serialize)]
+struct ProgramIdsData {
+    system_program: String,
+    token_program: String,
+    token_2022_program: String,
+    associated_token_program: String,
+    memo_program: String,
+}
+
+#[derive(Serialize)]
+struct VersionData {
+    version: String,
+    solana_sdk: String,
+    spl_token: String,
+    spl_token_2022: String,
+}
+
+#[derive(Serialize)]
+struct LimitsData {
+    max_body_bytes: u64,
+    max_batch_operations: usize,
+    max_mint_recipients: usize,
+    max_sol_recipients: usize,
+    max_pda_seeds: usize,
+    max_pda_seed_bytes: usize,
+    max_memo_bytes: usize,
+    max_transaction_bytes: usize,
+}
+
+#[derive(Serialize)]
+struct BatchData {
+    results: Vec<serde_json::Value>,
+    total_lamports: Option<u64>,
+    total_token_amount: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct CostBreakdownData {
+    signers: Vec<String>,
+    signature_fee_lamports: u64,
+    rent_lamports: u64,
+    total_lamports: u64,
+    total_sol: String,
+}
+
+#[derive(Serialize)]
+struct RentData {
+    space: u64,
+    lamports: u64,
+}
+
+#[derive(Serialize)]
+struct AccountRentData {
+    account: String,
+    space: u64,
+    lamports: u64,
+}
+
+#[derive(Serialize)]
+struct MintSizeData {
+    program_id: String,
+    space: u64,
+    rent_lamports: u64,
+    extensions: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TokenSetupCostData {
+    accounts: Vec<AccountRentData>,
+    total_lamports: u64,
+    total_sol: String,
+}
+
+#[derive(Serialize)]
+struct DecodedTokenInstructionData {
+    instruction: String,
+    fields: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct SolAmountData {
+    lamports: u64,
+    sol: String,
+    sol_trimmed: String,
+}
+
+#[derive(Serialize)]
+struct ValidationData {
+    valid: bool,
+}
+
 #[derive(Serialize)]
 struct SolTransferData {
     program_id: String,
+    // In system_instruction::transfer order: from (signer, writable), then to (writable)
     accounts: Vec<String>,
     instruction_data: String,
 }
 
+#[derive(Serialize)]
+struct SolMultiTransferData {
+    instructions: Vec<SolTransferData>,
+    total_lamports: u64,
+}
+
 #[derive(Serialize)]
 struct TokenTransferData {
     program_id: String,
     accounts: Vec<TokenAccountInfo>,
     instruction_data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ui_amount_string: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -77,339 +344,4729 @@ struct TokenAccountInfo {
     is_signer: bool,
 }
 
+// Built instructions still need a recent blockhash before the client can sign and submit them
+#[derive(Serialize)]
+struct UnsignedInstructionData<T> {
+    #[serde(flatten)]
+    instruction: T,
+    requires_recent_blockhash: bool,
+    recent_blockhash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_compute_units: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct BuiltTransactionData {
+    version: String,
+    transaction: String,
+    serialized_size: usize,
+    required_signatures: u8,
+    account_keys: Vec<AccountInfo>,
+    blockhash_placeholder: bool,
+}
+
+#[derive(Serialize)]
+struct TransactionSizeData {
+    serialized_size: usize,
+    max_size: usize,
+    exceeds_packet_limit: bool,
+}
+
+#[derive(Serialize)]
+struct PartiallySignedTransactionData {
+    transaction: String,
+    signer: String,
+    signatures_present: usize,
+    signatures_required: usize,
+    fully_signed: bool,
+}
+
+#[derive(Serialize)]
+struct TransactionSignatureCheckData {
+    valid: bool,
+    signer_pubkey: String,
+    signature: String,
+}
+
+#[derive(Serialize)]
+struct RequiredSignersData {
+    required_signers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_required_signer: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct DecodedTransactionData {
+    version: String,
+    fee_payer: String,
+    recent_blockhash: String,
+    signatures: Vec<String>,
+    instructions: Vec<InstructionData>,
+}
+
+
+// JavaScript clients send amounts above 2^53 as strings to avoid losing precision, so both
+// a JSON number and a numeric string are accepted
+fn u64_from_number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    struct U64Visitor;
+    
+    impl serde::de::Visitor<'_> for U64Visitor {
+        type Value = u64;
+        
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a non-negative integer or a numeric string")
+        }
+        
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+        
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<u64, E> {
+            u64::try_from(value).map_err(|_| E::custom(format!("{} must not be negative", value)))
+        }
+        
+        fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<u64, E> {
+            Err(E::custom(format!("{} must be a whole number of base units", value)))
+        }
+        
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<u64, E> {
+            if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+                return Err(E::custom(format!("\"{}\" is not a numeric string", value)));
+            }
+            value
+                .parse::<u64>()
+                .map_err(|_| E::custom(format!("\"{}\" exceeds the maximum of {}", value, u64::MAX)))
+        }
+    }
+    
+    deserializer.deserialize_any(U64Visitor)
+}
+
+// The same, for optional amounts; an omitted field still needs #[serde(default)]
+fn optional_u64_from_number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    struct NumberOrString(#[serde(deserialize_with = "u64_from_number_or_string")] u64);
+    
+    Ok(Option::<NumberOrString>::deserialize(deserializer)?.map(|NumberOrString(value)| value))
+}
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 struct CreateTokenRequest {
     #[serde(rename = "mintAuthority")]
     mint_authority: String,
-    mint: String,
+    #[serde(default)]
+    mint: Option<String>,
+    // Opt-in: with `mint` omitted, a fresh mint keypair is generated and its secret returned
+    #[serde(default)]
+    generate_mint: bool,
     decimals: u8,
+    #[serde(default)]
+    program_id: Option<String>,
+    #[serde(default)]
+    include_create_account: bool,
+    #[serde(default)]
+    payer: Option<String>,
+    // Overrides the mainnet Rent::default() funding for clusters with modified rent
+    #[serde(default, deserialize_with = "optional_u64_from_number_or_string")]
+    rent_lamports: Option<u64>,
+    // A pubkey, or "none" to opt out; omitted falls back to DEFAULT_FREEZE_AUTHORITY
+    #[serde(rename = "freezeAuthority", default)]
+    freeze_authority: Option<String>,
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 struct MintTokenRequest {
     mint: String,
     destination: String,
     authority: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
     amount: u64,
+    #[serde(default)]
+    decimals: Option<u8>,
+    #[serde(default)]
+    program_id: Option<String>,
 }
 
 #[derive(Deserialize)]
-struct SignMessageRequest {
-    message: String,
-    secret: String,
+#[serde(deny_unknown_fields)]
+struct MintRecipientInput {
+    destination: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    amount: u64,
 }
 
 #[derive(Deserialize)]
-struct VerifyMessageRequest {
-    message: String,
-    signature: String,
-    pubkey: String,
+#[serde(deny_unknown_fields)]
+struct MintBatchRequest {
+    mint: String,
+    authority: String,
+    recipients: Vec<MintRecipientInput>,
+    #[serde(default)]
+    decimals: Option<u8>,
+    #[serde(default)]
+    program_id: Option<String>,
 }
 
 #[derive(Deserialize)]
-struct SendSolRequest {
-    from: String,
-    to: String,
-    lamports: u64,
+#[serde(deny_unknown_fields)]
+struct MintCheckedRequest {
+    mint: String,
+    destination: String,
+    authority: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    amount: u64,
+    decimals: u8,
+    #[serde(default)]
+    program_id: Option<String>,
 }
 
 #[derive(Deserialize)]
-struct SendTokenRequest {
-    destination: String,
+#[serde(deny_unknown_fields)]
+struct BurnCheckedRequest {
+    account: String,
     mint: String,
-    owner: String,
+    authority: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
     amount: u64,
+    decimals: u8,
+    #[serde(default)]
+    program_id: Option<String>,
 }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CloseWithTransferRequest {
+    account: String,
+    owner: String,
+    destination: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    amount: u64,
+    // Receives the reclaimed rent; defaults to the owner
+    #[serde(default)]
+    rent_destination: Option<String>,
+    #[serde(default)]
+    decimals: Option<u8>,
+    #[serde(default)]
+    program_id: Option<String>,
+}
 
-type ApiResult = Result<Box<dyn warp::Reply>, warp::Rejection>;
-
-fn success_response<T: Serialize>(data: T) -> Box<dyn warp::Reply> {
-    Box::new(warp::reply::with_status(
-        warp::reply::json(&ApiResponse {
-            success: true,
-            data: Some(data),
-            error: None,
-        }),
-        warp::http::StatusCode::OK,
-    ))
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InitMultisigRequest {
+    account: String,
+    signers: Vec<String>,
+    m: u8,
+    #[serde(default)]
+    program_id: Option<String>,
 }
 
-fn error_response(message: &str) -> Box<dyn warp::Reply> {
-    Box::new(warp::reply::with_status(
-        warp::reply::json(&ApiResponse::<()> {
-            success: false,
-            data: None,
-            error: Some(message.to_string()),
-        }),
-        warp::http::StatusCode::BAD_REQUEST,
-    ))
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SyncNativeRequest {
+    account: String,
+    #[serde(default)]
+    program_id: Option<String>,
 }
 
-async fn generate_keypair() -> ApiResult {
-    let keypair = Keypair::new();
-    
-    let response_data = KeypairData {
-        pubkey: keypair.pubkey().to_string(),
-        secret: bs58::encode(&keypair.to_bytes()).into_string(),
-    };
-    
-    Ok(success_response(response_data))
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WrapSolRequest {
+    owner: String,
+    payer: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    lamports: u64,
 }
 
-async fn create_token(req: CreateTokenRequest) -> ApiResult {
-    
-    let mint_authority = match Pubkey::from_str(&req.mint_authority) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid mint authority address")),
-    };
-    
-    let mint_pubkey = match Pubkey::from_str(&req.mint) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid mint address")),
-    };
-    
-    let instruction = match token_instruction::initialize_mint(
-        &spl_token::id(),
-        &mint_pubkey,
-        &mint_authority,
-        None, 
-        req.decimals,
-    ) {
-        Ok(instruction) => instruction,
-        Err(_) => return Ok(error_response("Failed to create mint instruction")),
-    };
-    
-    let response_data = InstructionData {
-        program_id: spl_token::id().to_string(),
-        accounts: vec![
-            AccountInfo {
-                pubkey: mint_pubkey.to_string(),
-                is_signer: true,
-                is_writable: true,
-            },
-            AccountInfo {
-                pubkey: solana_sdk::sysvar::rent::id().to_string(),
-                is_signer: false,
-                is_writable: false,
-            },
-        ],
-        instruction_data: STANDARD.encode(&instruction.data),
-    };
-    
-    Ok(success_response(response_data))
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SignMessageRequest {
+    message: String,
+    secret: SecretKeyInput,
+    #[serde(default)]
+    signature_encoding: Option<String>,
+    #[serde(default)]
+    include_signature_bytes: bool,
+    // `message` is a hex SHA-256 digest whose 32 bytes are signed as-is
+    #[serde(default)]
+    prehashed: bool,
 }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MultiSignRequest {
+    message: String,
+    secrets: Vec<SecretKeyInput>,
+    #[serde(default)]
+    signature_encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SiwsSignRequest {
+    domain: String,
+    address: String,
+    #[serde(default)]
+    statement: Option<String>,
+    #[serde(default)]
+    uri: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    chain_id: Option<String>,
+    nonce: String,
+    issued_at: String,
+    #[serde(default)]
+    expiration_time: Option<String>,
+    secret: SecretKeyInput,
+    #[serde(default)]
+    include_signature_bytes: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SecretKeyInput {
+    Base58(String),
+    Bytes(Vec<i64>),
+}
+
+#[derive(Deserialize)]
+struct KeypairQuery {
+    index: Option<u64>,
+    include_secret: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+struct ComputeEstimateQuery {
+    #[serde(default)]
+    estimate_compute_units: bool,
+}
+
+#[derive(Deserialize)]
+struct KeypairStreamQuery {
+    count: Option<u32>,
+    include_secret: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PubkeyRequest {
+    pubkey: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeriveAddressRequest {
+    owner: String,
+    mint: String,
+    // Defaults to the classic token program; token-2022 mints need the token-2022 id here
+    #[serde(default)]
+    token_program: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SignChallengeRequest {
+    challenge: String,
+    #[serde(default)]
+    include_secret: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VerifyPairRequest {
+    pubkey: String,
+    secret: SecretKeyInput,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InspectKeypairRequest {
+    secret: String,
+    #[serde(default)]
+    encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathComponentInput {
+    index: u32,
+    hardened: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeriveKeypairRequest {
+    // Hex BIP39 seed (16 to 64 bytes), e.g. the output of mnemonic-to-seed
+    seed: String,
+    path: Vec<PathComponentInput>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyfileRequest {
+    keyfile: KeyfileInput,
+}
+
+// Accepts the keyfile either as the parsed byte array or as the raw `id.json` text
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyfileInput {
+    Bytes(Vec<i64>),
+    Text(String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VerifyMessageRequest {
+    message: String,
+    signature: String,
+    pubkey: String,
+    // "base58" (default) or "hex" for services that store raw 32-byte keys
+    #[serde(default)]
+    pubkey_encoding: Option<String>,
+    // Must match the `prehashed` flag the message was signed with
+    #[serde(default)]
+    prehashed: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Secp256k1VerifyRequest {
+    eth_address: String,
+    message: String,
+    signature: String,
+    recovery_id: u8,
+    #[serde(default)]
+    instruction_index: u8,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SendSolRequest {
+    from: String,
+    to: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    lamports: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SolRecipientInput {
+    to: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    lamports: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SendSolMultiRequest {
+    from: String,
+    recipients: Vec<SolRecipientInput>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CreateAccountRequest {
+    payer: String,
+    new_account: String,
+    owner: String,
+    space: u64,
+    // Defaults to the rent-exempt minimum for `space`
+    #[serde(default, deserialize_with = "optional_u64_from_number_or_string")]
+    lamports: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AllocateAssignRequest {
+    account: String,
+    space: u64,
+    owner: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WithdrawNonceRequest {
+    nonce_account: String,
+    authority: String,
+    destination: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    lamports: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SendTokenRequest {
+    destination: String,
+    mint: String,
+    owner: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    amount: u64,
+    #[serde(default)]
+    decimals: Option<u8>,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    program_id: Option<String>,
+    // Prepends an owner-funded idempotent create for the source ATA when the caller knows it is missing
+    #[serde(default)]
+    create_source_ata: bool,
+    // An SPL multisig that owns the source ATA and authorizes the transfer; `owner` then only
+    // pays for create_source_ata
+    #[serde(default)]
+    multisig_authority: Option<String>,
+    #[serde(default)]
+    signer_pubkeys: Vec<String>,
+    // Builds transfer_checked; the server can't read the mint, so `decimals` must come with
+    // `confirm_decimals: true` as the caller's acknowledgement that they checked it
+    #[serde(default)]
+    checked: bool,
+    #[serde(default)]
+    confirm_decimals: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SendTokenWithAtaRequest {
+    destination: String,
+    mint: String,
+    owner: String,
+    payer: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    amount: u64,
+    #[serde(default)]
+    decimals: Option<u8>,
+    #[serde(default)]
+    program_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BatchOperation {
+    CreateToken(CreateTokenRequest),
+    MintToken(MintTokenRequest),
+    SendSol(SendSolRequest),
+    SendToken(SendTokenRequest),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchRequest {
+    operations: Vec<BatchOperation>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CostBreakdownRequest {
+    operations: Vec<BatchOperation>,
+    #[serde(default)]
+    fee_payer: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RentMinimumRequest {
+    space: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MintSizeRequest {
+    #[serde(default)]
+    program_id: Option<String>,
+    #[serde(default)]
+    extensions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LamportsToSolRequest {
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    lamports: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AccountMetaInput {
+    pubkey: String,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InstructionInput {
+    program_id: String,
+    accounts: Vec<AccountMetaInput>,
+    instruction_data: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EncodeInstructionRequest {
+    program_id: String,
+    accounts: Vec<AccountMetaInput>,
+    data: String,
+    #[serde(default)]
+    data_encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DecodeTokenInstructionRequest {
+    data: String,
+    #[serde(default)]
+    data_encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LookupTableInput {
+    address: String,
+    addresses: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BuildTransactionRequest {
+    fee_payer: String,
+    recent_blockhash: String,
+    instructions: Vec<InstructionInput>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    address_lookup_tables: Vec<LookupTableInput>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RequiredSignersRequest {
+    message: String,
+    #[serde(default)]
+    pubkey: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DecodeTransactionRequest {
+    transaction: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VerifyTransactionSignatureRequest {
+    transaction: String,
+    signature_index: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartialSignRequest {
+    transaction: String,
+    secret: SecretKeyInput,
+}
+
+
+type ApiResult = Result<Box<dyn warp::Reply>, warp::Rejection>;
+
+struct ServerStats {
+    started_at: Instant,
+    requests_served: AtomicU64,
+}
+
+fn success_response<T: Serialize>(data: T) -> Box<dyn warp::Reply> {
+    success_response_with_warnings(data, Vec::new())
+}
+
+// Non-fatal advisories ride along with a successful response
+fn success_response_with_warnings<T: Serialize>(data: T, warnings: Vec<String>) -> Box<dyn warp::Reply> {
+    Box::new(warp::reply::with_status(
+        warp::reply::json(&ApiResponse {
+            success: true,
+            data: Some(data),
+            error: None,
+            errors: None,
+            code: None,
+            warnings,
+        }),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+fn error_response(message: &str) -> Box<dyn warp::Reply> {
+    error_response_with_status(message, warp::http::StatusCode::BAD_REQUEST)
+}
+
+fn error_response_with_status(message: &str, status: warp::http::StatusCode) -> Box<dyn warp::Reply> {
+    Box::new(warp::reply::with_status(
+        warp::reply::json(&ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some(message.to_string()),
+            errors: None,
+            code: None,
+            warnings: Vec::new(),
+        }),
+        status,
+    ))
+}
+
+// Reports every invalid field at once; `error` carries the first reason for older clients
+fn validation_error_response(errors: Vec<FieldError>) -> Box<dyn warp::Reply> {
+    Box::new(warp::reply::with_status(
+        warp::reply::json(&ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: errors.first().map(|error| error.reason.clone()),
+            errors: Some(errors),
+            code: None,
+            warnings: Vec::new(),
+        }),
+        warp::http::StatusCode::BAD_REQUEST,
+    ))
+}
+
+// Keeps the builder's ProgramError detail so clients can tell e.g. invalid decimals from other failures
+fn program_error_response(context: &str, error: ProgramError) -> Box<dyn warp::Reply> {
+    Box::new(warp::reply::with_status(
+        warp::reply::json(&ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some(format!("{}: {}", context, error)),
+            errors: None,
+            code: Some(u64::from(error)),
+            warnings: Vec::new(),
+        }),
+        warp::http::StatusCode::BAD_REQUEST,
+    ))
+}
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// Points at the first character outside the base58 alphabet, e.g. a `0` or `O` typo
+fn base58_alphabet_error(value: &str) -> Option<String> {
+    value
+        .chars()
+        .enumerate()
+        .find(|(_, c)| !BASE58_ALPHABET.contains(*c))
+        .map(|(position, c)| format!("invalid base58 character '{}' at position {}", c, position))
+}
+
+// Surrounding whitespace from copy-pasted addresses is ignored rather than reported as invalid
+fn normalize_pubkey_input(value: &str) -> &str {
+    value.trim()
+}
+
+fn parse_pubkey(value: &str, message: &str) -> Result<Pubkey, String> {
+    let value = normalize_pubkey_input(value);
+    Pubkey::from_str(value).map_err(|_| match base58_alphabet_error(value) {
+        Some(detail) => format!("{}: {}", message, detail),
+        None => message.to_string(),
+    })
+}
+
+fn resolve_program_id(program_id: Option<&str>, default: Pubkey) -> Result<Pubkey, String> {
+    match program_id {
+        Some(program_id) => parse_pubkey(program_id, "Invalid program id"),
+        None => Ok(default),
+    }
+}
+
+// Forks may deploy the token program elsewhere with its instruction layout unchanged. spl_token's
+// builders refuse any program id but their own, so they lay the instruction out and it is then
+// addressed to the resolved program, which the response reports from the instruction itself.
+fn for_token_program(mut instruction: Instruction, token_program: Pubkey) -> Instruction {
+    instruction.program_id = token_program;
+    instruction
+}
+
+fn validate_pubkey(value: &str, field: &str, reason: &str, errors: &mut Vec<FieldError>) -> Option<Pubkey> {
+    match parse_pubkey(value, reason) {
+        Ok(pubkey) => Some(pubkey),
+        Err(reason) => {
+            errors.push(FieldError {
+                field: field.to_string(),
+                reason,
+            });
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MissingBody;
+
+impl warp::reject::Reject for MissingBody {}
+
+#[derive(Debug)]
+struct BodyTooLarge(u64);
+
+impl warp::reject::Reject for BodyTooLarge {}
+
+#[derive(Debug)]
+struct InvalidBody(String);
+
+impl warp::reject::Reject for InvalidBody {}
+
+static LOG_BODIES: OnceLock<bool> = OnceLock::new();
+
+// Fraction of successful requests that are logged (access line, plus bodies with LOG_BODIES), from
+// LOG_SAMPLE_RATE; errors are always logged
+static LOG_SAMPLE_RATE: OnceLock<f64> = OnceLock::new();
+
+fn sampled_for_logging(status: warp::http::StatusCode) -> bool {
+    let rate = LOG_SAMPLE_RATE.get().copied().unwrap_or(1.0);
+    status.is_client_error() || status.is_server_error() || rate >= 1.0 || rand::random::<f64>() < rate
+}
+
+// Set from MAX_BODY_BYTES; every request here is a few keys or one transaction, far below the default
+const DEFAULT_MAX_BODY_BYTES: u64 = 64 * 1024;
+static MAX_BODY_BYTES: OnceLock<u64> = OnceLock::new();
+
+fn max_body_bytes() -> u64 {
+    MAX_BODY_BYTES.get().copied().unwrap_or(DEFAULT_MAX_BODY_BYTES)
+}
+
+// Set from MAX_BATCH_OPERATIONS; checked before any operation runs so an oversized array costs nothing but parsing
+const DEFAULT_MAX_BATCH_OPERATIONS: usize = 100;
+static MAX_BATCH_OPERATIONS: OnceLock<usize> = OnceLock::new();
+
+fn max_batch_operations() -> usize {
+    MAX_BATCH_OPERATIONS.get().copied().unwrap_or(DEFAULT_MAX_BATCH_OPERATIONS)
+}
+
+// Set from DERIVATION_CACHE_CAPACITY, where 0 turns the cache off. An ATA is a pure function of
+// owner, mint and token program but costs a hash-to-curve search, so repeated ones are served from
+// memory. Keys are public addresses only; nothing derived from a secret is ever cached.
+const DEFAULT_DERIVATION_CACHE_CAPACITY: usize = 1024;
+static DERIVATION_CACHE: OnceLock<DerivationCache> = OnceLock::new();
+
+// Owner, mint and token program
+type DerivationKey = (Pubkey, Pubkey, Pubkey);
+
+struct DerivationCache {
+    capacity: usize,
+    // Address plus the tick it was last read at, so the least recently used one is evicted
+    entries: Mutex<HashMap<DerivationKey, (Pubkey, u64)>>,
+    ticks: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl DerivationCache {
+    fn new(capacity: usize) -> Self {
+        DerivationCache {
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+            ticks: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+    
+    fn associated_token_address(&self, owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        let derive = || spl_associated_token_account::get_associated_token_address_with_program_id(owner, mint, token_program);
+        if self.capacity == 0 {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return derive();
+        }
+        
+        let key = (*owner, *mint, *token_program);
+        let tick = self.ticks.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((address, last_used)) = entries.get_mut(&key) {
+            *last_used = tick;
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return *address;
+        }
+        
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let address = derive();
+        if entries.len() >= self.capacity {
+            let least_recent = entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            if let Some(least_recent) = least_recent {
+                entries.remove(&least_recent);
+            }
+        }
+        entries.insert(key, (address, tick));
+        address
+    }
+}
+
+fn derivation_cache() -> &'static DerivationCache {
+    DERIVATION_CACHE.get_or_init(|| DerivationCache::new(DEFAULT_DERIVATION_CACHE_CAPACITY))
+}
+
+fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    derivation_cache().associated_token_address(owner, mint, token_program)
+}
+
+// Fields holding key material are masked wherever they appear in a logged body
+const REDACTED_FIELDS: [&str; 5] = ["secret", "secrets", "keyfile", "mint_secret", "seed"];
+
+fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if REDACTED_FIELDS.contains(&name.as_str()) {
+                    *field = serde_json::Value::String("[REDACTED]".to_string());
+                } else {
+                    redact_secrets(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+fn contains_secrets(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Object(fields) => fields
+            .iter()
+            .any(|(name, field)| (REDACTED_FIELDS.contains(&name.as_str()) && !field.is_null()) || contains_secrets(field)),
+        serde_json::Value::Array(items) => items.iter().any(contains_secrets),
+        _ => false,
+    }
+}
+
+// Bodies that aren't JSON are never printed, since they can't be redacted
+fn log_body(label: &str, path: &str, body: &[u8]) {
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_secrets(&mut value);
+            eprintln!("🐛 {} {}: {}", label, path, value);
+        }
+        Err(_) => eprintln!("🐛 {} {}: <{} bytes, not JSON>", label, path, body.len()),
+    }
+}
+
+// Per-request state from json_body that is applied once the handler has replied
+struct RequestContext {
+    // Set by `"echo": true` in the body or an `X-Echo: true` header; holds the redacted request
+    echo: Option<serde_json::Value>,
+    // Raw body kept for LOG_BODIES so it is logged under the same sampling decision as the response
+    logged_body: Option<warp::hyper::body::Bytes>,
+    fingerprint: RequestFingerprint,
+}
+
+// Carried on the response (never sent to the client) so log_response can print the request
+#[derive(Clone)]
+struct LoggedRequestBody(warp::hyper::body::Bytes);
+
+// Hash of the canonical request body, carried on the response so store_idempotent_response can
+// tie an Idempotency-Key to the body it was first sent with
+#[derive(Clone, Copy, PartialEq)]
+struct RequestFingerprint(u64);
+
+// Object keys are sorted so a retry that reorders or reformats the same JSON still matches
+fn canonical_json(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.into_iter().map(|(key, value)| (key, canonical_json(value))).collect();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(fields.into_iter().collect())
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(canonical_json).collect()),
+        other => other,
+    }
+}
+
+fn request_fingerprint(body: &[u8]) -> RequestFingerprint {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(value) => hasher.write(&serde_json::to_vec(&canonical_json(value)).unwrap_or_default()),
+        Err(_) => hasher.write(body),
+    }
+    RequestFingerprint(hasher.finish())
+}
+
+// Reads chunk by chunk and stops as soon as the limit is passed, so a chunked body without a
+// Content-Length is refused without ever being buffered whole
+async fn read_limited_body(
+    body: impl futures::Stream<Item = Result<impl warp::hyper::body::Buf, warp::Error>>,
+) -> Result<warp::hyper::body::Bytes, warp::Rejection> {
+    let limit = max_body_bytes();
+    let mut collected = Vec::new();
+    let mut body = Box::pin(body);
+    while let Some(chunk) = body.next().await {
+        let mut chunk = chunk.map_err(|err| warp::reject::custom(InvalidBody(err.to_string())))?;
+        if (collected.len() + chunk.remaining()) as u64 > limit {
+            return Err(warp::reject::custom(BodyTooLarge(limit)));
+        }
+        while chunk.has_remaining() {
+            let part = chunk.chunk();
+            let length = part.len();
+            collected.extend_from_slice(part);
+            chunk.advance(length);
+        }
+    }
+    Ok(collected.into())
+}
+
+// Like warp::body::json, but distinguishes an empty body from malformed JSON
+fn json_body<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T, RequestContext), Error = warp::Rejection> + Clone {
+    // A declared Content-Length over the limit is refused before the body is read
+    let within_limit = warp::header::optional::<u64>("content-length")
+        .and_then(|length: Option<u64>| async move {
+            match length {
+                Some(length) if length > max_body_bytes() => Err(warp::reject::custom(BodyTooLarge(max_body_bytes()))),
+                _ => Ok(()),
+            }
+        })
+        .untuple_one();
+    
+    within_limit
+        .and(warp::path::full())
+        .and(warp::header::optional::<String>("x-echo"))
+        .and(warp::body::stream().and_then(read_limited_body))
+        .and_then(|path: warp::path::FullPath, echo_header: Option<String>, body: warp::hyper::body::Bytes| async move {
+            let log_bodies = LOG_BODIES.get().copied().unwrap_or(false);
+            // Rejected bodies are errors, which are always logged
+            let reject = |rejection: warp::Rejection| {
+                if log_bodies {
+                    log_body("request", path.as_str(), &body);
+                }
+                Err(rejection)
+            };
+            if body.iter().all(u8::is_ascii_whitespace) {
+                return reject(warp::reject::custom(MissingBody));
+            }
+            
+            // `echo` isn't part of any request struct, so it is taken out before the strict parse
+            let mut value = serde_json::from_slice::<serde_json::Value>(&body).ok();
+            let echo_field = value
+                .as_mut()
+                .and_then(serde_json::Value::as_object_mut)
+                .and_then(|fields| fields.remove("echo"));
+            let parsed = match (&echo_field, value.clone()) {
+                (Some(serde_json::Value::Bool(_)), Some(value)) => serde_json::from_value::<T>(value),
+                (Some(_), _) => return reject(warp::reject::custom(InvalidBody("echo must be a boolean".to_string()))),
+                (None, _) => serde_json::from_slice::<T>(&body),
+            };
+            let req = match parsed {
+                Ok(req) => req,
+                Err(err) => return reject(warp::reject::custom(InvalidBody(err.to_string()))),
+            };
+            
+            let echo_requested = echo_field == Some(serde_json::Value::Bool(true))
+                || echo_header.is_some_and(|header| header.eq_ignore_ascii_case("true"));
+            let echo = value.filter(|_| echo_requested).map(|mut value| {
+                redact_secrets(&mut value);
+                value
+            });
+            Ok((req, RequestContext {
+                echo,
+                fingerprint: request_fingerprint(&body),
+                logged_body: log_bodies.then_some(body),
+            }))
+        })
+        .untuple_one()
+}
+
+// Adds the echoed request under `request` in the handler's JSON envelope and hands the
+// request body on for logging
+async fn attach_request_context(context: RequestContext, handler: impl Future<Output = ApiResult>) -> ApiResult {
+    let reply = handler.await?;
+    let mut response = warp::Reply::into_response(reply);
+    response.extensions_mut().insert(context.fingerprint);
+    if let Some(body) = context.logged_body {
+        response.extensions_mut().insert(LoggedRequestBody(body));
+    }
+    let Some(request) = context.echo else {
+        return Ok(Box::new(response));
+    };
+    
+    let (parts, body) = response.into_parts();
+    let body_bytes = match warp::hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(Box::new(warp::reply::Response::from_parts(parts, warp::hyper::Body::empty()))),
+    };
+    let encoded = match serde_json::from_slice::<serde_json::Value>(&body_bytes) {
+        Ok(serde_json::Value::Object(mut envelope)) => {
+            envelope.insert("request".to_string(), request);
+            serde_json::to_vec(&envelope).ok()
+        }
+        _ => None,
+    };
+    
+    match encoded {
+        Some(encoded) => Ok(Box::new(warp::reply::Response::from_parts(parts, encoded.into()))),
+        None => Ok(Box::new(warp::reply::Response::from_parts(parts, body_bytes.into()))),
+    }
+}
+
+static REQUEST_TIMEOUT: OnceLock<Option<Duration>> = OnceLock::new();
+
+// Cancellation signal for the handler run_handler is driving. Loops that can run long check it
+// between iterations, since a synchronous loop never reaches an await where the timeout could
+// drop it, and work moved to the blocking pool keeps running after its future is dropped.
+#[derive(Clone, Default)]
+struct Cancellation {
+    deadline: Option<Instant>,
+    dropped: Arc<AtomicBool>,
+}
+
+impl Cancellation {
+    fn is_cancelled(&self) -> bool {
+        self.dropped.load(Ordering::Relaxed) || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+// Sets the flag when the future holding it is dropped, whether it finished, timed out, or the
+// client went away
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+tokio::task_local! {
+    static CANCELLATION: Cancellation;
+}
+
+// Outside run_handler (e.g. in tests) nothing is ever cancelled
+fn cancellation() -> Cancellation {
+    CANCELLATION.try_with(Cancellation::clone).unwrap_or_default()
+}
+
+fn deadline_exceeded_response() -> Box<dyn warp::Reply> {
+    error_response_with_status("Request deadline exceeded", warp::http::StatusCode::GATEWAY_TIMEOUT)
+}
+
+// Freeze authority used by /token/create when the request doesn't name one
+#[derive(Clone, Copy)]
+enum DefaultFreezeAuthority {
+    None,
+    MintAuthority,
+    Pubkey(Pubkey),
+}
+
+static DEFAULT_FREEZE_AUTHORITY: OnceLock<DefaultFreezeAuthority> = OnceLock::new();
+
+// Caps handlers running at once across all connections; unset means unlimited
+static CONCURRENCY_LIMIT: OnceLock<Option<Semaphore>> = OnceLock::new();
+
+// Set from MAX_CPU_BOUND_TASKS, defaulting to one per core. CPU-heavy work runs on the blocking pool
+// under these permits, so a burst of it queues up there instead of stalling the async workers
+// that serve cheap endpoints.
+static CPU_BOUND_LIMIT: OnceLock<Arc<Semaphore>> = OnceLock::new();
+
+fn cpu_bound_limit() -> Arc<Semaphore> {
+    CPU_BOUND_LIMIT
+        .get_or_init(|| Arc::new(Semaphore::new(default_cpu_bound_tasks())))
+        .clone()
+}
+
+fn default_cpu_bound_tasks() -> usize {
+    std::thread::available_parallelism().map(|cores| cores.get()).unwrap_or(4)
+}
+
+async fn cpu_bound_permit() -> tokio::sync::OwnedSemaphorePermit {
+    cpu_bound_limit().acquire_owned().await.expect("CPU-bound semaphore is never closed")
+}
+
+// The permit moves into the blocking task, so it stays held even if the handler is dropped on timeout.
+// Panics are resumed here for run_handler to turn into a 500.
+async fn run_cpu_bound<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    let permit = cpu_bound_permit().await;
+    let task = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        work()
+    });
+    match task.await {
+        Ok(value) => value,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+// A CONCURRENCY_LIMIT permit, or None when no limit is configured; Err is the 503 reply
+fn request_permit() -> Result<Option<tokio::sync::SemaphorePermit<'static>>, Box<dyn warp::Reply>> {
+    match CONCURRENCY_LIMIT.get().and_then(Option::as_ref) {
+        Some(semaphore) => semaphore.try_acquire().map(Some).map_err(|_| {
+            error_response_with_status(
+                "Server is at capacity, retry shortly",
+                warp::http::StatusCode::SERVICE_UNAVAILABLE,
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+async fn run_handler(handler: impl Future<Output = ApiResult>) -> ApiResult {
+    let _permit = match request_permit() {
+        Ok(permit) => permit,
+        Err(reply) => return Ok(reply),
+    };
+    run_guarded(handler).await
+}
+
+// For handlers whose response body keeps working after they return: the permit is handed to the
+// handler so the body can hold it until it finishes
+async fn run_handler_with_permit<F: Future<Output = ApiResult>>(
+    handler: impl FnOnce(Option<tokio::sync::SemaphorePermit<'static>>) -> F,
+) -> ApiResult {
+    match request_permit() {
+        Ok(permit) => run_guarded(handler(permit)).await,
+        Err(reply) => Ok(reply),
+    }
+}
+
+// Runs a handler under the configured deadline and turns panics into a 500 envelope instead of
+// a dropped connection. Dropping the handler future on timeout (or when hyper drops it because
+// the client went away) cancels any work still pending at its next await point, and sets the
+// Cancellation that long loops inside the handler check.
+async fn run_guarded(handler: impl Future<Output = ApiResult>) -> ApiResult {
+    let timeout = REQUEST_TIMEOUT.get().copied().flatten();
+    let cancellation = Cancellation {
+        deadline: timeout.map(|timeout| Instant::now() + timeout),
+        dropped: Arc::new(AtomicBool::new(false)),
+    };
+    let _cancel_on_drop = CancelOnDrop(cancellation.dropped.clone());
+    let handler = CANCELLATION.scope(cancellation, AssertUnwindSafe(handler).catch_unwind());
+    let outcome = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, handler).await {
+            Ok(outcome) => outcome,
+            Err(_) => return Ok(deadline_exceeded_response()),
+        },
+        None => handler.await,
+    };
+    
+    match outcome {
+        Ok(result) => result,
+        Err(panic) => {
+            let reason = panic
+                .downcast_ref::<&str>()
+                .map(|reason| reason.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            eprintln!("❌ Handler panicked: {}", reason);
+            Ok(error_response_with_status(
+                "Internal server error",
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            ))
+        }
+    }
+}
+
+async fn handle_rejection(err: warp::Rejection) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    if err.find::<MissingBody>().is_some() {
+        return Ok(error_response("request body is required and must be valid JSON"));
+    }
+    if let Some(InvalidBody(message)) = err.find::<InvalidBody>() {
+        return Ok(error_response(&format!("Invalid JSON body: {}", message)));
+    }
+    if let Some(BodyTooLarge(limit)) = err.find::<BodyTooLarge>() {
+        return Ok(error_response_with_status(
+            &format!("Request body exceeds the {}-byte limit", limit),
+            warp::http::StatusCode::PAYLOAD_TOO_LARGE,
+        ));
+    }
+    if err.find::<warp::reject::InvalidQuery>().is_some() {
+        return Ok(error_response("Invalid query string"));
+    }
+    if err.is_not_found() {
+        return Ok(error_response_with_status("Not found", warp::http::StatusCode::NOT_FOUND));
+    }
+    if err.find::<warp::reject::MethodNotAllowed>().is_some() {
+        return Ok(error_response_with_status(
+            "Method not allowed",
+            warp::http::StatusCode::METHOD_NOT_ALLOWED,
+        ));
+    }
+    Err(err)
+}
+
+fn decode_secret(secret: &SecretKeyInput) -> Result<Vec<u8>, String> {
+    match secret {
+        SecretKeyInput::Base58(encoded) => bs58::decode(encoded).into_vec().map_err(|_| {
+            match base58_alphabet_error(encoded) {
+                Some(detail) => format!("Invalid secret key format: {}", detail),
+                None => "Invalid secret key format".to_string(),
+            }
+        }),
+        SecretKeyInput::Bytes(values) => {
+            if values.len() != 64 {
+                return Err(format!(
+                    "Secret key byte array must contain exactly 64 values, got {}",
+                    values.len()
+                ));
+            }
+            values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    u8::try_from(*value).map_err(|_| {
+                        format!("Secret key byte at index {} must be between 0 and 255", index)
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+// Checked instructions fail on-chain when decimals differ from the mint, which this offline server cannot read
+fn checked_decimals_warning(decimals: u8) -> String {
+    format!(
+        "decimals ({}) must match the mint's on-chain decimals; the server cannot verify this offline",
+        decimals
+    )
+}
+
+// Formats a raw token amount with the mint's decimals using string math, trimming trailing zeros
+fn format_ui_amount(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+fn with_blockhash_hint<T: Serialize>(instruction: T) -> UnsignedInstructionData<T> {
+    UnsignedInstructionData {
+        instruction,
+        requires_recent_blockhash: true,
+        recent_blockhash: String::new(),
+        estimated_compute_units: None,
+    }
+}
+
+// Conservative per-instruction compute unit figures, rounded up from typical mainnet usage.
+// They are heuristics for fee budgeting, not a simulation result.
+const SYSTEM_INSTRUCTION_CU: u32 = 300;
+const INITIALIZE_MINT_CU: u32 = 3_000;
+const MINT_TO_CU: u32 = 4_600;
+const MINT_TO_CHECKED_CU: u32 = 4_700;
+const BURN_CHECKED_CU: u32 = 4_900;
+const TOKEN_TRANSFER_CU: u32 = 4_700;
+const CREATE_ATA_CU: u32 = 25_000;
+const SYNC_NATIVE_CU: u32 = 3_100;
+const CLOSE_ACCOUNT_CU: u32 = 2_900;
+const INITIALIZE_MULTISIG_CU: u32 = 3_100;
+
+impl<T> UnsignedInstructionData<T> {
+    fn with_compute_estimate(mut self, query: &ComputeEstimateQuery, units: u32) -> Self {
+        if query.estimate_compute_units {
+            self.estimated_compute_units = Some(units);
+        }
+        self
+    }
+}
+
+fn accepts_media_type(accept: &Option<String>, media_types: &[&str]) -> bool {
+    accept.as_deref().is_some_and(|accept| {
+        accept.split(',').any(|media_type| {
+            let media_type = media_type.split(';').next().unwrap_or("").trim();
+            media_types.iter().any(|wanted| media_type.eq_ignore_ascii_case(wanted))
+        })
+    })
+}
+
+// Set from ERROR_FORMAT=problem+json to send RFC 7807 errors without an Accept header
+static PROBLEM_JSON_ERRORS: OnceLock<bool> = OnceLock::new();
+
+// Rewrites an error envelope as an RFC 7807 problem; field errors and warnings become extension members
+fn problem_details(status: warp::http::StatusCode, envelope: &serde_json::Value) -> serde_json::Value {
+    let mut problem = json!({
+        "type": "about:blank",
+        "title": status.canonical_reason().unwrap_or("Error"),
+        "status": status.as_u16(),
+        "detail": envelope["error"],
+    });
+    for extension in ["errors", "code", "warnings"] {
+        if let Some(value) = envelope.get(extension) {
+            problem[extension] = value.clone();
+        }
+    }
+    problem
+}
+
+#[derive(Deserialize)]
+struct FormatQuery {
+    pretty: Option<String>,
+    envelope: Option<String>,
+    instruction_format: Option<String>,
+    data_encodings: Option<String>,
+    include_program_name: Option<String>,
+}
+
+// Per-request encoding preferences applied to the envelope after the handler has run
+struct ResponseOptions {
+    msgpack: bool,
+    pretty: bool,
+    problem_json: bool,
+    // `?envelope=false` drops the {success,data,error} wrapper and lets the status code carry the outcome
+    bare: bool,
+    // `?instruction_format=web3js` emits instructions as @solana/web3.js TransactionInstruction JSON
+    web3_instructions: bool,
+    // `?data_encodings=base64,hex` turns each instruction_data string into an object keyed by encoding
+    data_encodings: Option<Vec<String>>,
+    // `?include_program_name=true` adds a display name next to each instruction's program_id
+    program_names: bool,
+}
+
+// Mounts every route under BASE_PATH, e.g. "/solana" for a reverse proxy without path rewriting
+fn base_path_filter(segments: &[String]) -> BoxedFilter<()> {
+    segments
+        .iter()
+        .fold(warp::any().boxed(), |filter, segment| {
+            filter.and(warp::path(segment.clone())).boxed()
+        })
+}
+
+// DISABLED_ENDPOINTS names that stand for a set of routes rather than a path prefix. "sign" covers every
+// route that takes a secret key, wherever it lives in the path tree.
+const ENDPOINT_GROUPS: [(&str, &[&str]); 1] = [(
+    "sign",
+    &["sign", "message/sign", "message/sign-siws", "keypair/sign-challenge", "transaction/partial-sign"],
+)];
+
+fn disabled_endpoint_prefixes(entry: &str) -> Vec<Vec<String>> {
+    let paths = match ENDPOINT_GROUPS.iter().find(|(name, _)| *name == entry.trim()) {
+        Some((_, paths)) => paths.to_vec(),
+        None => vec![entry],
+    };
+    paths
+        .into_iter()
+        .map(|path| {
+            path.split('/')
+                .map(str::trim)
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .filter(|segments| !segments.is_empty())
+        .collect()
+}
+
+// Rejects requests whose path (below BASE_PATH) starts with a DISABLED_ENDPOINTS entry, e.g. "keypair" or "message/sign"
+fn endpoint_guard(base_segments: usize, disabled: Arc<Vec<Vec<String>>>) -> BoxedFilter<()> {
+    warp::path::full()
+        .and_then(move |path: warp::path::FullPath| {
+            let disabled = disabled.clone();
+            async move {
+                let segments: Vec<&str> = path
+                    .as_str()
+                    .split('/')
+                    .filter(|segment| !segment.is_empty())
+                    .skip(base_segments)
+                    .collect();
+                let is_disabled = disabled.iter().any(|prefix| {
+                    prefix.len() <= segments.len() && prefix.iter().zip(&segments).all(|(a, b)| a == b)
+                });
+                if is_disabled {
+                    Err(warp::reject::not_found())
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .untuple_one()
+        .boxed()
+}
+
+fn response_options() -> impl Filter<Extract = (ResponseOptions,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("accept")
+        .and(warp::query::<FormatQuery>())
+        .map(|accept: Option<String>, query: FormatQuery| ResponseOptions {
+            msgpack: accepts_media_type(&accept, &["application/msgpack", "application/x-msgpack"]),
+            pretty: query.pretty.as_deref() == Some("true"),
+            problem_json: accepts_media_type(&accept, &["application/problem+json"])
+                || PROBLEM_JSON_ERRORS.get().copied().unwrap_or(false),
+            bare: query.envelope.as_deref() == Some("false"),
+            web3_instructions: query.instruction_format.as_deref() == Some("web3js"),
+            program_names: query.include_program_name.as_deref() == Some("true"),
+            data_encodings: query.data_encodings.map(|encodings| {
+                encodings
+                    .split(',')
+                    .map(|encoding| encoding.trim().to_string())
+                    .filter(|encoding| !encoding.is_empty())
+                    .collect()
+            }),
+        })
+}
+
+const IDEMPOTENCY_TTL: Duration = Duration::from_secs(60);
+const IDEMPOTENCY_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
+struct CachedResponse {
+    stored_at: Instant,
+    fingerprint: RequestFingerprint,
+    status: warp::http::StatusCode,
+    content_type: Option<warp::http::HeaderValue>,
+    body: warp::hyper::body::Bytes,
+}
+
+type IdempotencyCache = Arc<Mutex<HashMap<String, CachedResponse>>>;
+
+// Scopes an Idempotency-Key to the POST path it was sent with. Keypair routes are never
+// cached, and neither is any other response carrying a secret (e.g. /token/create with
+// generate_mint), so generated secrets are not kept in memory.
+fn idempotency_key(base_segments: usize) -> impl Filter<Extract = (Option<String>,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("idempotency-key")
+        .and(warp::method())
+        .and(warp::path::full())
+        .map(move |key: Option<String>, method: warp::http::Method, path: warp::path::FullPath| {
+            let is_keypair_route = path
+                .as_str()
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .nth(base_segments)
+                == Some("keypair");
+            key.filter(|_| method == warp::http::Method::POST && !is_keypair_route)
+                .map(|key| format!("{} {}", path.as_str(), key))
+        })
+}
+
+// Rejects without touching the body when nothing is cached, so the request falls through to its route
+async fn cached_idempotent_response(
+    key: Option<String>,
+    cache: IdempotencyCache,
+) -> Result<CachedResponse, warp::Rejection> {
+    let Some(key) = key else {
+        return Err(warp::reject());
+    };
+    let entries = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    entries
+        .get(&key)
+        .filter(|cached| cached.stored_at.elapsed() < IDEMPOTENCY_TTL)
+        .cloned()
+        .ok_or_else(warp::reject)
+}
+
+// A key reused with a different body is refused rather than answered with the first body's response
+async fn replay_idempotent_response(
+    cached: CachedResponse,
+    body: impl futures::Stream<Item = Result<impl warp::hyper::body::Buf, warp::Error>>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let body = match read_limited_body(body).await {
+        Ok(body) => body,
+        Err(rejection) => return handle_rejection(rejection).await.map(warp::Reply::into_response),
+    };
+    if request_fingerprint(&body) != cached.fingerprint {
+        return Ok(warp::Reply::into_response(error_response_with_status(
+            "Idempotency-Key was already used with a different request body",
+            warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+        )));
+    }
+    
+    let mut response = warp::reply::Response::new(cached.body.clone().into());
+    *response.status_mut() = cached.status;
+    if let Some(content_type) = &cached.content_type {
+        response.headers_mut().insert(warp::http::header::CONTENT_TYPE, content_type.clone());
+    }
+    response.headers_mut().insert(
+        "idempotent-replayed",
+        warp::http::HeaderValue::from_static("true"),
+    );
+    Ok(response)
+}
+
+// Only successful responses are stored, so a retry after a failure is recomputed
+async fn store_idempotent_response(
+    key: Option<String>,
+    cache: IdempotencyCache,
+    reply: impl warp::Reply,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let response = reply.into_response();
+    let Some(key) = key.filter(|_| response.status().is_success()) else {
+        return Ok(response);
+    };
+    
+    // Routes that don't read a JSON body are keyed to an empty one
+    let fingerprint = response
+        .extensions()
+        .get::<RequestFingerprint>()
+        .copied()
+        .unwrap_or_else(|| request_fingerprint(&[]));
+    let (parts, body) = response.into_parts();
+    let body_bytes = match warp::hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(warp::reply::Response::from_parts(parts, warp::hyper::Body::empty())),
+    };
+    let has_secrets = serde_json::from_slice::<serde_json::Value>(&body_bytes).is_ok_and(|value| contains_secrets(&value));
+    if has_secrets {
+        return Ok(warp::reply::Response::from_parts(parts, body_bytes.into()));
+    }
+    
+    let mut entries = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    entries.retain(|_, cached| cached.stored_at.elapsed() < IDEMPOTENCY_TTL);
+    if entries.len() >= IDEMPOTENCY_CAPACITY {
+        let oldest = entries
+            .iter()
+            .min_by_key(|(_, cached)| cached.stored_at)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            entries.remove(&oldest);
+        }
+    }
+    entries.insert(
+        key,
+        CachedResponse {
+            stored_at: Instant::now(),
+            fingerprint,
+            status: parts.status,
+            content_type: parts.headers.get(warp::http::header::CONTENT_TYPE).cloned(),
+            body: body_bytes.clone(),
+        },
+    );
+    drop(entries);
+    
+    Ok(warp::reply::Response::from_parts(parts, body_bytes.into()))
+}
+
+// One sampling decision per request covers both the access line and, with LOG_BODIES, the bodies
+async fn log_response(
+    started: Instant,
+    method: warp::http::Method,
+    path: warp::path::FullPath,
+    response: warp::reply::Response,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    if !sampled_for_logging(response.status()) {
+        return Ok(response);
+    }
+    eprintln!(
+        "📨 {} {} {} {}ms",
+        method,
+        path.as_str(),
+        response.status().as_u16(),
+        started.elapsed().as_millis()
+    );
+    if !LOG_BODIES.get().copied().unwrap_or(false) {
+        return Ok(response);
+    }
+    if let Some(LoggedRequestBody(body)) = response.extensions().get::<LoggedRequestBody>() {
+        log_body("request", path.as_str(), body);
+    }
+    let is_json = response
+        .headers()
+        .get(warp::http::header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"application/json"));
+    if !is_json {
+        eprintln!("🐛 response {}: <{} streamed or non-JSON body>", path.as_str(), response.status());
+        return Ok(response);
+    }
+    
+    let (parts, body) = response.into_parts();
+    let body_bytes = match warp::hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(warp::reply::Response::from_parts(parts, warp::hyper::Body::empty())),
+    };
+    log_body(&format!("response {}", parts.status.as_u16()), path.as_str(), &body_bytes);
+    
+    Ok(warp::reply::Response::from_parts(parts, body_bytes.into()))
+}
+
+fn program_name(program_id: &str) -> &'static str {
+    match Pubkey::from_str(program_id) {
+        Ok(id) if id == solana_sdk::system_program::id() => "System Program",
+        Ok(id) if id == spl_token::id() => "Token Program",
+        Ok(id) if id == spl_token_2022::id() => "Token-2022 Program",
+        Ok(id) if id == spl_associated_token_account::id() => "Associated Token Account Program",
+        Ok(id) if id == spl_memo::id() => "Memo Program",
+        _ => "Unknown",
+    }
+}
+
+fn add_program_names(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let name = match (fields.get("program_id"), fields.contains_key("instruction_data")) {
+                (Some(serde_json::Value::String(program_id)), true) => Some(program_name(program_id)),
+                _ => None,
+            };
+            match name {
+                Some(name) => {
+                    fields.insert("program_name".to_string(), serde_json::Value::String(name.to_string()));
+                }
+                None => fields.values_mut().for_each(add_program_names),
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(add_program_names),
+        _ => {}
+    }
+}
+
+const DATA_ENCODINGS: [&str; 3] = ["base64", "hex", "base58"];
+
+// Replaces every base64 instruction_data string with {encoding: value} for the requested encodings
+fn expand_instruction_data(value: &mut serde_json::Value, encodings: &[String]) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                let bytes = match field {
+                    serde_json::Value::String(data) if name == "instruction_data" => STANDARD.decode(data.as_str()).ok(),
+                    _ => None,
+                };
+                let Some(bytes) = bytes else {
+                    expand_instruction_data(field, encodings);
+                    continue;
+                };
+                let encoded = encodings
+                    .iter()
+                    .map(|encoding| {
+                        let value = match encoding.as_str() {
+                            "hex" => hex::encode(&bytes),
+                            "base58" => bs58::encode(&bytes).into_string(),
+                            _ => STANDARD.encode(&bytes),
+                        };
+                        (encoding.clone(), serde_json::Value::String(value))
+                    })
+                    .collect();
+                *field = serde_json::Value::Object(encoded);
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| expand_instruction_data(item, encodings)),
+        _ => {}
+    }
+}
+
+// Rewrites every {program_id, accounts, instruction_data} object into web3.js's {keys, programId, data}
+fn to_web3_instructions(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let is_instruction = ["program_id", "accounts", "instruction_data"]
+                .iter()
+                .all(|name| fields.contains_key(*name));
+            if !is_instruction {
+                fields.values_mut().for_each(to_web3_instructions);
+                return;
+            }
+            let keys: Vec<serde_json::Value> = fields
+                .remove("accounts")
+                .and_then(|accounts| accounts.as_array().cloned())
+                .unwrap_or_default()
+                .into_iter()
+                .enumerate()
+                .map(|(index, account)| match account {
+                    // SOL transfers list bare pubkeys: the signing sender, then the recipient, both writable
+                    serde_json::Value::String(pubkey) => json!({
+                        "pubkey": pubkey,
+                        "isSigner": index == 0,
+                        "isWritable": true,
+                    }),
+                    // Token transfers only flag the owner as signer; the source and destination are writable
+                    account if account.get("is_signer").is_none() => json!({
+                        "pubkey": account["pubkey"],
+                        "isSigner": account["isSigner"],
+                        "isWritable": account["isSigner"] == serde_json::Value::Bool(false),
+                    }),
+                    account => json!({
+                        "pubkey": account["pubkey"],
+                        "isSigner": account["is_signer"],
+                        "isWritable": account["is_writable"],
+                    }),
+                })
+                .collect();
+            let program_id = fields.remove("program_id").unwrap_or_default();
+            let data = fields.remove("instruction_data").unwrap_or_default();
+            fields.insert("keys".to_string(), serde_json::Value::Array(keys));
+            fields.insert("programId".to_string(), program_id);
+            fields.insert("data".to_string(), data);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(to_web3_instructions),
+        _ => {}
+    }
+}
+
+// Re-encodes the JSON envelope produced by a handler as MessagePack or pretty-printed JSON
+// Answers an unknown `?data_encodings` value before any handler runs, so the work is never done or
+// cached. It stands in for the handler's reply and still goes through problem+json and pretty printing.
+fn unsupported_data_encoding() -> impl Filter<Extract = (warp::reply::Response,), Error = warp::Rejection> + Clone {
+    response_options().and_then(|options: ResponseOptions| async move {
+        let unsupported_encoding = options
+            .data_encodings
+            .iter()
+            .flatten()
+            .find(|encoding| !DATA_ENCODINGS.contains(&encoding.as_str()));
+        match unsupported_encoding {
+            Some(encoding) => Ok(warp::Reply::into_response(error_response(&format!(
+                "Unsupported data encoding \"{}\", expected any of {}",
+                encoding,
+                DATA_ENCODINGS.join(", ")
+            )))),
+            None => Err(warp::reject()),
+        }
+    })
+}
+
+async fn negotiate_response(
+    options: ResponseOptions,
+    reply: impl warp::Reply,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let response = reply.into_response();
+    let is_json = response
+        .headers()
+        .get(warp::http::header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"application/json"));
+    let is_problem = options.problem_json && (response.status().is_client_error() || response.status().is_server_error());
+    if !is_json
+        || !(options.msgpack
+            || options.pretty
+            || is_problem
+            || options.bare
+            || options.web3_instructions
+            || options.program_names
+            || options.data_encodings.is_some())
+    {
+        return Ok(response);
+    }
+    
+    let (mut parts, body) = response.into_parts();
+    let body_bytes = match warp::hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(warp::reply::Response::from_parts(parts, warp::hyper::Body::empty())),
+    };
+    
+    let mut value = match serde_json::from_slice::<serde_json::Value>(&body_bytes) {
+        Ok(value) => value,
+        Err(_) => return Ok(warp::reply::Response::from_parts(parts, body_bytes.into())),
+    };
+    
+    if let Some(data) = value.get_mut("data").filter(|_| options.program_names) {
+        add_program_names(data);
+    }
+    if let (Some(data), Some(encodings)) = (value.get_mut("data"), &options.data_encodings) {
+        expand_instruction_data(data, encodings);
+    }
+    if let Some(data) = value.get_mut("data").filter(|_| options.web3_instructions) {
+        to_web3_instructions(data);
+    }
+    
+    if is_problem {
+        parts.headers.insert(
+            warp::http::header::CONTENT_TYPE,
+            warp::http::HeaderValue::from_static("application/problem+json"),
+        );
+        let problem = problem_details(parts.status, &value);
+        let encoded = if options.pretty {
+            serde_json::to_vec_pretty(&problem)
+        } else {
+            serde_json::to_vec(&problem)
+        };
+        return match encoded {
+            Ok(encoded) => Ok(warp::reply::Response::from_parts(parts, encoded.into())),
+            Err(_) => Ok(warp::reply::Response::from_parts(parts, body_bytes.into())),
+        };
+    }
+    
+    // Successes become the bare payload, with any warnings moved to X-Warnings (one header per
+    // warning); errors keep their detail fields minus the success flag
+    let value = match value {
+        serde_json::Value::Object(mut envelope) if options.bare => match envelope.remove("data") {
+            Some(data) if parts.status.is_success() => {
+                let warnings = envelope.remove("warnings");
+                for warning in warnings.iter().filter_map(serde_json::Value::as_array).flatten() {
+                    let header = warning
+                        .as_str()
+                        .and_then(|warning| warp::http::HeaderValue::from_bytes(warning.as_bytes()).ok());
+                    if let Some(header) = header {
+                        parts.headers.append("x-warnings", header);
+                    }
+                }
+                data
+            }
+            _ => {
+                envelope.remove("success");
+                serde_json::Value::Object(envelope)
+            }
+        },
+        value => value,
+    };
+    
+    let encoded = if options.msgpack {
+        parts.headers.insert(
+            warp::http::header::CONTENT_TYPE,
+            warp::http::HeaderValue::from_static("application/msgpack"),
+        );
+        rmp_serde::to_vec_named(&value).ok()
+    } else if options.pretty {
+        serde_json::to_vec_pretty(&value).ok()
+    } else {
+        serde_json::to_vec(&value).ok()
+    };
+    
+    match encoded {
+        Some(encoded) => Ok(warp::reply::Response::from_parts(parts, encoded.into())),
+        None => Ok(warp::reply::Response::from_parts(parts, body_bytes.into())),
+    }
+}
+
+// Derives the keypair for `index` as SHA-256(master seed || index), so it is stable across restarts
+fn derive_indexed_keypair(master_seed: &str, index: u64) -> Option<Keypair> {
+    let seed = hashv(&[master_seed.as_bytes(), &index.to_le_bytes()]);
+    keypair_from_seed(seed.as_ref()).ok()
+}
+
+const MAX_KEYGEN_ATTEMPTS: u32 = 8;
+
+// Guards against a faulty RNG: an all-zero seed, or a pubkey that is all zeros or the
+// Ed25519 identity point, is discarded and regenerated
+fn generate_sound_keypair() -> Option<Keypair> {
+    let mut identity = [0u8; 32];
+    identity[0] = 1;
+    (0..MAX_KEYGEN_ATTEMPTS).map(|_| Keypair::new()).find(|keypair| {
+        let pubkey = keypair.pubkey().to_bytes();
+        keypair.secret().as_bytes().iter().any(|byte| *byte != 0) && pubkey != [0u8; 32] && pubkey != identity
+    })
+}
+
+async fn generate_keypair(query: KeypairQuery, master_seed: Option<String>) -> ApiResult {
+    let keypair = match query.index {
+        Some(index) => {
+            let Some(master_seed) = master_seed else {
+                return Ok(error_response("Indexed keypairs require KEYPAIR_MASTER_SEED to be set"));
+            };
+            match derive_indexed_keypair(&master_seed, index) {
+                Some(keypair) => keypair,
+                None => return Ok(error_response("Failed to derive keypair")),
+            }
+        }
+        None => match generate_sound_keypair() {
+            Some(keypair) => keypair,
+            None => {
+                return Ok(error_response_with_status(
+                    "Failed to generate a sound keypair",
+                    warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+                ))
+            }
+        },
+    };
+    
+    let response_data = KeypairData {
+        pubkey: keypair.pubkey().to_string(),
+        secret: query
+            .include_secret
+            .unwrap_or(true)
+            .then(|| bs58::encode(&keypair.to_bytes()).into_string()),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+const MAX_STREAMED_KEYPAIRS: u32 = 100_000;
+const STREAMED_KEYPAIRS_PER_CHUNK: u32 = 256;
+
+// Streams newline-delimited KeypairData so large batches are never held in memory. The body holds the
+// request's CONCURRENCY_LIMIT permit until the last chunk is sent or the client goes away.
+async fn stream_keypairs(query: KeypairStreamQuery, permit: Option<tokio::sync::SemaphorePermit<'static>>) -> ApiResult {
+    let count = query.count.unwrap_or(10);
+    if count == 0 || count > MAX_STREAMED_KEYPAIRS {
+        return Ok(error_response(&format!(
+            "count must be between 1 and {}",
+            MAX_STREAMED_KEYPAIRS
+        )));
+    }
+    
+    // Each chunk is generated on the blocking pool under its own permit, which is released before the
+    // chunk is handed to the client, so a slow reader never holds CPU-bound capacity while it waits.
+    // Chunks are only generated as the client reads. The body outlives run_handler, so it carries its
+    // own drop flag, which stops a chunk already on the blocking pool once the client goes away.
+    let include_secret = query.include_secret.unwrap_or(true);
+    let state = (count, CancelOnDrop(Arc::new(AtomicBool::new(false))), permit);
+    let lines = futures::stream::unfold(state, move |(remaining, cancel_on_drop, permit)| async move {
+        if remaining == 0 {
+            return None;
+        }
+        let chunk_size = remaining.min(STREAMED_KEYPAIRS_PER_CHUNK);
+        let dropped = cancel_on_drop.0.clone();
+        let chunk = run_cpu_bound(move || {
+            let mut chunk = String::new();
+            for _ in 0..chunk_size {
+                if dropped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let keypair = Keypair::new();
+                let keypair_data = KeypairData {
+                    pubkey: keypair.pubkey().to_string(),
+                    secret: include_secret.then(|| bs58::encode(&keypair.to_bytes()).into_string()),
+                };
+                chunk += &serde_json::to_string(&keypair_data)?;
+                chunk.push('\n');
+            }
+            Ok::<_, serde_json::Error>(chunk)
+        })
+        .await;
+        Some((chunk, (remaining - chunk_size, cancel_on_drop, permit)))
+    });
+    
+    let mut response = warp::reply::Response::new(warp::hyper::Body::wrap_stream(lines));
+    response.headers_mut().insert(
+        warp::http::header::CONTENT_TYPE,
+        warp::http::HeaderValue::from_static("application/x-ndjson"),
+    );
+    Ok(Box::new(response))
+}
+
+// BIP32 caps paths at 255 levels
+const MAX_DERIVATION_DEPTH: usize = 255;
+
+// SLIP-0010 Ed25519 derivation, which only defines hardened children
+async fn derive_keypair(req: DeriveKeypairRequest) -> ApiResult {
+    
+    let seed = match hex::decode(req.seed.trim().trim_start_matches("0x")) {
+        Ok(seed) => seed,
+        Err(_) => return Ok(error_response("Invalid seed, expected hex")),
+    };
+    if !(16..=64).contains(&seed.len()) {
+        return Ok(error_response(&format!("Seed must be 16 to 64 bytes, got {}", seed.len())));
+    }
+    
+    if req.path.len() > MAX_DERIVATION_DEPTH {
+        return Ok(error_response(&format!("Derivation path cannot be deeper than {}", MAX_DERIVATION_DEPTH)));
+    }
+    
+    let mut errors = Vec::new();
+    for (position, component) in req.path.iter().enumerate() {
+        if component.index >= 1 << 31 {
+            errors.push(FieldError {
+                field: format!("path[{}].index", position),
+                reason: format!("Index {} must be below 2^31; hardening is set with the hardened flag", component.index),
+            });
+        }
+        if !component.hardened {
+            errors.push(FieldError {
+                field: format!("path[{}].hardened", position),
+                reason: "SLIP-0010 Ed25519 only supports hardened derivation".to_string(),
+            });
+        }
+    }
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    let derivation_path = std::iter::once("m".to_string())
+        .chain(req.path.iter().map(|component| format!("{}'", component.index)))
+        .collect::<Vec<_>>()
+        .join("/");
+    
+    // Up to 255 HMAC-SHA512 rounds per request
+    let path = derivation_path.clone();
+    let derived = run_cpu_bound(move || {
+        DerivationPath::from_absolute_path_str(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|path| keypair_from_seed_and_derivation_path(&seed, Some(path)).map_err(|err| err.to_string()))
+    })
+    .await;
+    let keypair = match derived {
+        Ok(keypair) => keypair,
+        Err(message) => return Ok(error_response(&format!("Failed to derive keypair: {}", message))),
+    };
+    
+    let response_data = DerivedKeypairData {
+        pubkey: keypair.pubkey().to_string(),
+        secret: bs58::encode(&keypair.to_bytes()).into_string(),
+        derivation_path,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn keypair_from_keyfile(req: KeyfileRequest) -> ApiResult {
+    
+    let values = match req.keyfile {
+        KeyfileInput::Bytes(values) => values,
+        KeyfileInput::Text(text) => match serde_json::from_str::<Vec<i64>>(&text) {
+            Ok(values) => values,
+            Err(_) => return Ok(error_response("Keyfile must be a JSON array of 64 bytes")),
+        },
+    };
+    
+    let secret_bytes = match decode_secret(&SecretKeyInput::Bytes(values)) {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let keypair = match Keypair::from_bytes(&secret_bytes) {
+        Ok(kp) => kp,
+        Err(_) => return Ok(error_response("Invalid secret key")),
+    };
+    
+    let response_data = PubkeyData {
+        pubkey: keypair.pubkey().to_string(),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn inspect_keypair(req: InspectKeypairRequest) -> ApiResult {
+    
+    let decoded = match req.encoding.as_deref().unwrap_or("base58") {
+        "base58" => bs58::decode(req.secret.trim()).into_vec().map_err(|_| {
+            match base58_alphabet_error(req.secret.trim()) {
+                Some(detail) => format!("Secret is not valid base58: {}", detail),
+                None => "Secret is not valid base58".to_string(),
+            }
+        }),
+        "hex" => hex::decode(req.secret.trim()).map_err(|_| "Secret is not valid hex".to_string()),
+        _ => return Ok(error_response("Unsupported encoding, expected base58 or hex")),
+    };
+    let bytes = match decoded {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    // A 64-byte keypair is only valid when its trailing 32 bytes are the pubkey of its leading seed
+    let (derived_pubkey, is_valid_keypair, hint) = match bytes.len() {
+        64 => match keypair_from_seed(&bytes[..32]) {
+            Ok(kp) if kp.pubkey().to_bytes()[..] == bytes[32..] => (Some(kp.pubkey().to_string()), true, None),
+            _ => (None, false, Some("Trailing 32 bytes do not match the public key of the leading seed".to_string())),
+        },
+        32 => (
+            keypair_from_seed(&bytes).ok().map(|kp| kp.pubkey().to_string()),
+            false,
+            Some("Got a 32-byte seed, not a 64-byte keypair; derived_pubkey is the key this seed produces".to_string()),
+        ),
+        len => (None, false, Some(format!("Expected a 64-byte keypair, got {} bytes", len))),
+    };
+    
+    let response_data = KeypairInspectData {
+        byte_length: bytes.len(),
+        is_valid_keypair,
+        derived_pubkey,
+        hint,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+const MAX_OFF_CURVE_ATTEMPTS: u32 = 256;
+
+// Roughly half of random 32-byte values are not valid Ed25519 points, so this finishes in a few tries
+async fn off_curve_pubkey() -> ApiResult {
+    let cancellation = cancellation();
+    for attempts in 1..=MAX_OFF_CURVE_ATTEMPTS {
+        if cancellation.is_cancelled() {
+            return Ok(deadline_exceeded_response());
+        }
+        let candidate = Pubkey::new_from_array(rand::random());
+        if !candidate.is_on_curve() {
+            let response_data = OffCurveData {
+                pubkey: candidate.to_string(),
+                attempts,
+            };
+            return Ok(success_response(response_data));
+        }
+    }
+    
+    Ok(error_response(&format!("No off-curve value found in {} attempts", MAX_OFF_CURVE_ATTEMPTS)))
+}
+
+async fn pubkey_encodings(req: PubkeyRequest) -> ApiResult {
+    
+    let pubkey = match parse_pubkey(&req.pubkey, "Invalid public key") {
+        Ok(pk) => pk,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let bytes = pubkey.to_bytes();
+    let response_data = PubkeyEncodingsData {
+        base58: pubkey.to_string(),
+        hex: hex::encode(bytes),
+        bytes: bytes.to_vec(),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+// The ATA address depends on the token program, so a token-2022 mint derived with the classic id points nowhere
+async fn derive_address(req: DeriveAddressRequest) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let owner = validate_pubkey(&req.owner, "owner", "Invalid owner address", &mut errors);
+    let mint = validate_pubkey(&req.mint, "mint", "Invalid mint address", &mut errors);
+    let token_program = match req.token_program.as_deref() {
+        Some(program_id) => validate_pubkey(program_id, "token_program", "Invalid token program id", &mut errors),
+        None => Some(spl_token::id()),
+    };
+    
+    let (Some(owner), Some(mint), Some(token_program)) = (owner, mint, token_program) else {
+        return Ok(validation_error_response(errors));
+    };
+    
+    let address = associated_token_address(&owner, &mint, &token_program);
+    let mut warnings = Vec::new();
+    if token_program != spl_token::id() && token_program != spl_token_2022::id() {
+        warnings.push(format!("{} is neither the token nor the token-2022 program", token_program));
+    }
+    
+    let response_data = DerivedAddressData {
+        address: address.to_string(),
+        owner: owner.to_string(),
+        mint: mint.to_string(),
+        token_program: token_program.to_string(),
+    };
+    
+    Ok(success_response_with_warnings(response_data, warnings))
+}
+
+async fn normalize_pubkey(req: PubkeyRequest) -> ApiResult {
+    
+    let pubkey = match parse_pubkey(&req.pubkey, "Invalid public key") {
+        Ok(pk) => pk,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let canonical = pubkey.to_string();
+    let response_data = NormalizedPubkeyData {
+        changed: canonical != req.pubkey,
+        pubkey: canonical,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn sign_challenge(req: SignChallengeRequest) -> ApiResult {
+    
+    if req.challenge.is_empty() {
+        return Ok(error_response("Challenge must not be empty"));
+    }
+    
+    let keypair = Keypair::new();
+    let signature = keypair.sign_message(req.challenge.as_bytes());
+    
+    let response_data = ChallengeSignatureData {
+        pubkey: keypair.pubkey().to_string(),
+        signature: STANDARD.encode(signature.as_ref()),
+        secret: req
+            .include_secret
+            .then(|| bs58::encode(&keypair.to_bytes()).into_string()),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn verify_keypair_pair(req: VerifyPairRequest) -> ApiResult {
+    
+    let pubkey = match parse_pubkey(&req.pubkey, "Invalid public key") {
+        Ok(pk) => pk,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let secret_bytes = match decode_secret(&req.secret) {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let keypair = match Keypair::from_bytes(&secret_bytes) {
+        Ok(kp) => kp,
+        Err(_) => return Ok(error_response("Invalid secret key")),
+    };
+    
+    let response_data = PairMatchData {
+        matches: keypair.pubkey() == pubkey,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn create_token(req: CreateTokenRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let mint_authority = validate_pubkey(&req.mint_authority, "mintAuthority", "Invalid mint authority address", &mut errors);
+    let generated_mint = match (req.mint.as_deref(), req.generate_mint) {
+        (None, true) => Some(Keypair::new()),
+        (Some(_), true) => {
+            errors.push(FieldError {
+                field: "generate_mint".to_string(),
+                reason: "generate_mint cannot be combined with an explicit mint".to_string(),
+            });
+            None
+        }
+        (None, false) => {
+            errors.push(FieldError {
+                field: "mint".to_string(),
+                reason: "mint is required unless generate_mint is set".to_string(),
+            });
+            None
+        }
+        (Some(_), false) => None,
+    };
+    let mint_pubkey = match (&generated_mint, req.mint.as_deref()) {
+        (Some(keypair), _) => Some(keypair.pubkey()),
+        (None, Some(mint)) => validate_pubkey(mint, "mint", "Invalid mint address", &mut errors),
+        (None, None) => None,
+    };
+    let token_program = match req.program_id.as_deref() {
+        Some(program_id) => validate_pubkey(program_id, "program_id", "Invalid program id", &mut errors),
+        None => Some(spl_token::id()),
+    };
+    
+    let payer = match (req.include_create_account, req.payer.as_deref()) {
+        (true, Some(payer)) => validate_pubkey(payer, "payer", "Invalid payer address", &mut errors),
+        (true, None) => {
+            errors.push(FieldError {
+                field: "payer".to_string(),
+                reason: "payer is required when include_create_account is set".to_string(),
+            });
+            None
+        }
+        (false, _) => None,
+    };
+    
+    if req.rent_lamports == Some(0) {
+        errors.push(FieldError {
+            field: "rent_lamports".to_string(),
+            reason: "rent_lamports must be greater than 0".to_string(),
+        });
+    }
+    
+    let freeze_authority = match req.freeze_authority.as_deref() {
+        Some("none") => Some(None),
+        Some(freeze_authority) => {
+            validate_pubkey(freeze_authority, "freezeAuthority", "Invalid freeze authority address", &mut errors)
+                .map(Some)
+        }
+        None => Some(None),
+    };
+    
+    let (Some(mint_authority), Some(mint_pubkey), Some(token_program), Some(freeze_authority)) =
+        (mint_authority, mint_pubkey, token_program, freeze_authority)
+    else {
+        return Ok(validation_error_response(errors));
+    };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    let freeze_authority = match (req.freeze_authority.is_some(), DEFAULT_FREEZE_AUTHORITY.get()) {
+        (false, Some(DefaultFreezeAuthority::MintAuthority)) => Some(mint_authority),
+        (false, Some(DefaultFreezeAuthority::Pubkey(pubkey))) => Some(*pubkey),
+        _ => freeze_authority,
+    };
+    
+    let instruction = match token_instruction::initialize_mint(
+        &spl_token::id(),
+        &mint_pubkey,
+        &mint_authority,
+        freeze_authority.as_ref(),
+        req.decimals,
+    ) {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create mint instruction", error)),
+    };
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: vec![
+            AccountInfo {
+                pubkey: mint_pubkey.to_string(),
+                is_signer: true,
+                is_writable: true,
+            },
+            AccountInfo {
+                pubkey: solana_sdk::sysvar::rent::id().to_string(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ],
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: None,
+    };
+    
+    if let Some(payer) = payer {
+        // The mint account has to exist, rent-exempt and owned by the token program, before initialize_mint
+        let create_account = system_instruction::create_account(
+            &payer,
+            &mint_pubkey,
+            req.rent_lamports
+                .unwrap_or_else(|| Rent::default().minimum_balance(spl_token::state::Mint::LEN)),
+            spl_token::state::Mint::LEN as u64,
+            &token_program,
+        );
+        let create_account_data = InstructionData {
+            program_id: create_account.program_id.to_string(),
+            accounts: instruction_accounts(&create_account),
+            instruction_data: STANDARD.encode(&create_account.data),
+            ui_amount_string: None,
+        };
+        
+        let sequence = CreateTokenData {
+            instruction: InstructionSequenceData {
+                instructions: vec![create_account_data, response_data],
+            },
+            mint: generated_mint.as_ref().map(|keypair| keypair.pubkey().to_string()),
+            mint_secret: generated_mint.as_ref().map(|keypair| keypair.to_base58_string()),
+        };
+        return Ok(success_response(
+            with_blockhash_hint(sequence).with_compute_estimate(&estimate, SYSTEM_INSTRUCTION_CU + INITIALIZE_MINT_CU),
+        ));
+    }
+    
+    let response_data = CreateTokenData {
+        instruction: response_data,
+        mint: generated_mint.as_ref().map(|keypair| keypair.pubkey().to_string()),
+        mint_secret: generated_mint.as_ref().map(|keypair| keypair.to_base58_string()),
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data).with_compute_estimate(&estimate, INITIALIZE_MINT_CU)))
+}
+
+
+async fn mint_token(req: MintTokenRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    // Validate all addresses
+    let mint = match parse_pubkey(&req.mint, "Invalid mint address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let destination = match parse_pubkey(&req.destination, "Invalid destination address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let authority = match parse_pubkey(&req.authority, "Invalid authority address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let token_program = match resolve_program_id(req.program_id.as_deref(), spl_token::id()) {
+        Ok(program_id) => program_id,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+  
+    let instruction = match token_instruction::mint_to(
+        &spl_token::id(),
+        &mint,
+        &destination,
+        &authority,
+        &[],
+        req.amount,
+    ) {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create mint instruction", error)),
+    };
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: vec![
+            AccountInfo {
+                pubkey: mint.to_string(),
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountInfo {
+                pubkey: destination.to_string(),
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountInfo {
+                pubkey: authority.to_string(),
+                is_signer: true,
+                is_writable: false,
+            },
+        ],
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: req.decimals.map(|decimals| format_ui_amount(req.amount, decimals)),
+    };
+    
+    let warnings = vec![
+        "mint_to does not check decimals; use /token/mint-checked to avoid a decimal mismatch".to_string(),
+    ];
+    
+    Ok(success_response_with_warnings(with_blockhash_hint(response_data).with_compute_estimate(&estimate, MINT_TO_CU), warnings))
+}
+
+const MAX_MINT_RECIPIENTS: usize = 64;
+
+async fn mint_token_batch(req: MintBatchRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    if req.recipients.is_empty() {
+        return Ok(error_response("At least one recipient is required"));
+    }
+    if req.recipients.len() > MAX_MINT_RECIPIENTS {
+        return Ok(error_response(&format!("At most {} recipients are allowed per request", MAX_MINT_RECIPIENTS)));
+    }
+    
+    let mint = match parse_pubkey(&req.mint, "Invalid mint address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let authority = match parse_pubkey(&req.authority, "Invalid authority address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let token_program = match resolve_program_id(req.program_id.as_deref(), spl_token::id()) {
+        Ok(program_id) => program_id,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    // Each recipient is built independently so one bad destination doesn't fail the airdrop
+    let results: Vec<MintBatchEntry> = req
+        .recipients
+        .iter()
+        .enumerate()
+        .map(|(index, recipient)| {
+            let instruction = parse_pubkey(&recipient.destination, "Invalid destination address")
+                .and_then(|destination| match recipient.amount {
+                    0 => Err("Amount must be greater than 0".to_string()),
+                    amount => token_instruction::mint_to(&spl_token::id(), &mint, &destination, &authority, &[], amount)
+                        .map(|instruction| for_token_program(instruction, token_program))
+                        .map_err(|error| format!("Failed to create mint instruction: {}", error)),
+                });
+            match instruction {
+                Ok(instruction) => MintBatchEntry {
+                    index,
+                    instruction: Some(InstructionData {
+                        program_id: instruction.program_id.to_string(),
+                        accounts: instruction_accounts(&instruction),
+                        instruction_data: STANDARD.encode(&instruction.data),
+                        ui_amount_string: req.decimals.map(|decimals| format_ui_amount(recipient.amount, decimals)),
+                    }),
+                    error: None,
+                },
+                Err(message) => MintBatchEntry {
+                    index,
+                    instruction: None,
+                    error: Some(message),
+                },
+            }
+        })
+        .collect();
+    let compute_units = MINT_TO_CU * results.iter().filter(|entry| entry.instruction.is_some()).count() as u32;
+    
+    let response_data = MintBatchData { results };
+    
+    Ok(success_response(with_blockhash_hint(response_data).with_compute_estimate(&estimate, compute_units)))
+}
+
+fn instruction_accounts(instruction: &Instruction) -> Vec<AccountInfo> {
+    instruction
+        .accounts
+        .iter()
+        .map(|meta| AccountInfo {
+            pubkey: meta.pubkey.to_string(),
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+        })
+        .collect()
+}
+
+async fn mint_token_checked(req: MintCheckedRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mint = match parse_pubkey(&req.mint, "Invalid mint address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let destination = match parse_pubkey(&req.destination, "Invalid destination address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let authority = match parse_pubkey(&req.authority, "Invalid authority address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let token_program = match resolve_program_id(req.program_id.as_deref(), spl_token::id()) {
+        Ok(program_id) => program_id,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let instruction = match token_instruction::mint_to_checked(
+        &spl_token::id(),
+        &mint,
+        &destination,
+        &authority,
+        &[],
+        req.amount,
+        req.decimals,
+    ) {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create mint instruction", error)),
+    };
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: instruction_accounts(&instruction),
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: Some(format_ui_amount(req.amount, req.decimals)),
+    };
+    
+    Ok(success_response_with_warnings(
+        with_blockhash_hint(response_data).with_compute_estimate(&estimate, MINT_TO_CHECKED_CU),
+        vec![checked_decimals_warning(req.decimals)],
+    ))
+}
+
+async fn burn_token_checked(req: BurnCheckedRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let account = match parse_pubkey(&req.account, "Invalid token account address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let mint = match parse_pubkey(&req.mint, "Invalid mint address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let authority = match parse_pubkey(&req.authority, "Invalid authority address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let token_program = match resolve_program_id(req.program_id.as_deref(), spl_token::id()) {
+        Ok(program_id) => program_id,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    if req.amount == 0 {
+        return Ok(error_response("Amount must be greater than 0"));
+    }
+    
+    // The token program rejects the burn if decimals don't match the mint
+    let instruction = match token_instruction::burn_checked(
+        &spl_token::id(),
+        &account,
+        &mint,
+        &authority,
+        &[],
+        req.amount,
+        req.decimals,
+    ) {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create burn instruction", error)),
+    };
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: instruction_accounts(&instruction),
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: Some(format_ui_amount(req.amount, req.decimals)),
+    };
+    
+    Ok(success_response_with_warnings(
+        with_blockhash_hint(response_data).with_compute_estimate(&estimate, BURN_CHECKED_CU),
+        vec![checked_decimals_warning(req.decimals)],
+    ))
+}
+
+// A token account can only be closed once empty, so any remaining balance is moved out first
+async fn close_with_transfer(req: CloseWithTransferRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let account = validate_pubkey(&req.account, "account", "Invalid token account address", &mut errors);
+    let owner = validate_pubkey(&req.owner, "owner", "Invalid owner address", &mut errors);
+    let destination = validate_pubkey(&req.destination, "destination", "Invalid destination token account address", &mut errors);
+    let rent_destination = match req.rent_destination.as_deref() {
+        Some(rent_destination) => {
+            validate_pubkey(rent_destination, "rent_destination", "Invalid rent destination address", &mut errors)
+        }
+        None => owner,
+    };
+    let token_program = match req.program_id.as_deref() {
+        Some(program_id) => validate_pubkey(program_id, "program_id", "Invalid program id", &mut errors),
+        None => Some(spl_token::id()),
+    };
+    
+    let (Some(account), Some(owner), Some(destination), Some(rent_destination), Some(token_program)) =
+        (account, owner, destination, rent_destination, token_program)
+    else {
+        return Ok(validation_error_response(errors));
+    };
+    if account == destination {
+        errors.push(FieldError {
+            field: "destination".to_string(),
+            reason: "Destination must differ from the account being closed".to_string(),
+        });
+    }
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    let mut instructions = Vec::with_capacity(2);
+    let mut compute_units = CLOSE_ACCOUNT_CU;
+    let mut warnings = Vec::new();
+    
+    // An empty account skips straight to the close
+    if req.amount > 0 {
+        let transfer = match token_instruction::transfer(&spl_token::id(), &account, &destination, &owner, &[], req.amount) {
+            Ok(instruction) => for_token_program(instruction, token_program),
+            Err(error) => return Ok(program_error_response("Failed to create transfer instruction", error)),
+        };
+        instructions.push(InstructionData {
+            program_id: transfer.program_id.to_string(),
+            accounts: instruction_accounts(&transfer),
+            instruction_data: STANDARD.encode(&transfer.data),
+            ui_amount_string: req.decimals.map(|decimals| format_ui_amount(req.amount, decimals)),
+        });
+        compute_units += TOKEN_TRANSFER_CU;
+        warnings.push("The close fails unless amount is the account's entire remaining balance".to_string());
+    }
+    
+    let close = match token_instruction::close_account(&spl_token::id(), &account, &rent_destination, &owner, &[]) {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create close account instruction", error)),
+    };
+    instructions.push(InstructionData {
+        program_id: close.program_id.to_string(),
+        accounts: instruction_accounts(&close),
+        instruction_data: STANDARD.encode(&close.data),
+        ui_amount_string: None,
+    });
+    
+    let response_data = InstructionSequenceData { instructions };
+    
+    Ok(success_response_with_warnings(
+        with_blockhash_hint(response_data).with_compute_estimate(&estimate, compute_units),
+        warnings,
+    ))
+}
+
+async fn init_multisig(req: InitMultisigRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let account = validate_pubkey(&req.account, "account", "Invalid multisig account address", &mut errors);
+    let token_program = match req.program_id.as_deref() {
+        Some(program_id) => validate_pubkey(program_id, "program_id", "Invalid program id", &mut errors),
+        None => Some(spl_token::id()),
+    };
+    
+    let mut signers: Vec<Pubkey> = Vec::with_capacity(req.signers.len());
+    for (index, signer) in req.signers.iter().enumerate() {
+        let field = format!("signers[{}]", index);
+        if let Some(signer) = validate_pubkey(signer, &field, "Invalid signer address", &mut errors) {
+            if signers.contains(&signer) {
+                errors.push(FieldError {
+                    field,
+                    reason: "Duplicate signer".to_string(),
+                });
+            }
+            signers.push(signer);
+        }
+    }
+    
+    // The token program stores at most MAX_SIGNERS keys in a multisig account
+    if req.signers.is_empty() || req.signers.len() > spl_token::instruction::MAX_SIGNERS {
+        errors.push(FieldError {
+            field: "signers".to_string(),
+            reason: format!("A multisig needs between 1 and {} signers", spl_token::instruction::MAX_SIGNERS),
+        });
+    }
+    if req.m == 0 || req.m as usize > req.signers.len() {
+        errors.push(FieldError {
+            field: "m".to_string(),
+            reason: format!("m must be between 1 and the number of signers ({})", req.signers.len()),
+        });
+    }
+    
+    let (Some(account), Some(token_program)) = (account, token_program) else {
+        return Ok(validation_error_response(errors));
+    };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+    let instruction = match token_instruction::initialize_multisig(&spl_token::id(), &account, &signer_refs, req.m) {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create initialize multisig instruction", error)),
+    };
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: instruction_accounts(&instruction),
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: None,
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data).with_compute_estimate(&estimate, INITIALIZE_MULTISIG_CU)))
+}
+
+async fn sync_native(req: SyncNativeRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let account = match parse_pubkey(&req.account, "Invalid token account address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let token_program = match resolve_program_id(req.program_id.as_deref(), spl_token::id()) {
+        Ok(program_id) => program_id,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    // Updates the wrapped SOL balance after lamports were sent straight to the token account
+    let instruction = match token_instruction::sync_native(&spl_token::id(), &account) {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create sync native instruction", error)),
+    };
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: instruction_accounts(&instruction),
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: None,
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data).with_compute_estimate(&estimate, SYNC_NATIVE_CU)))
+}
 
-async fn mint_token(req: MintTokenRequest) -> ApiResult {
-    // Validate all addresses
-    let mint = match Pubkey::from_str(&req.mint) {
+// Creates the owner's WSOL account if needed, moves the owner's lamports into it, then syncs the token balance
+async fn wrap_sol(req: WrapSolRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let owner = validate_pubkey(&req.owner, "owner", "Invalid owner address", &mut errors);
+    let payer = validate_pubkey(&req.payer, "payer", "Invalid payer address", &mut errors);
+    
+    if req.lamports == 0 {
+        errors.push(FieldError {
+            field: "lamports".to_string(),
+            reason: "Lamports amount must be greater than 0".to_string(),
+        });
+    }
+    
+    let (Some(owner), Some(payer)) = (owner, payer) else {
+        return Ok(validation_error_response(errors));
+    };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    let native_mint = spl_token::native_mint::id();
+    let wsol_account = associated_token_address(&owner, &native_mint, &spl_token::id());
+    
+    let create_ata = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        &payer,
+        &owner,
+        &native_mint,
+        &spl_token::id(),
+    );
+    let transfer = system_instruction::transfer(&owner, &wsol_account, req.lamports);
+    let sync = match token_instruction::sync_native(&spl_token::id(), &wsol_account) {
+        Ok(instruction) => instruction,
+        Err(error) => return Ok(program_error_response("Failed to create sync native instruction", error)),
+    };
+    
+    let response_data = InstructionSequenceData {
+        instructions: [create_ata, transfer, sync]
+            .iter()
+            .map(|instruction| InstructionData {
+                program_id: instruction.program_id.to_string(),
+                accounts: instruction_accounts(instruction),
+                instruction_data: STANDARD.encode(&instruction.data),
+                ui_amount_string: None,
+            })
+            .collect(),
+    };
+    
+    Ok(success_response(
+        with_blockhash_hint(response_data)
+            .with_compute_estimate(&estimate, CREATE_ATA_CU + SYSTEM_INSTRUCTION_CU + SYNC_NATIVE_CU),
+    ))
+}
+
+
+const PREHASH_LEN: usize = 32;
+
+// Prehashed messages are signed and verified over the digest bytes, never over the hex text
+fn message_bytes(message: &str, prehashed: bool) -> Result<Vec<u8>, String> {
+    if !prehashed {
+        return Ok(message.as_bytes().to_vec());
+    }
+    let digest = hex::decode(message.trim().trim_start_matches("0x"))
+        .map_err(|_| "Invalid prehash, expected hex".to_string())?;
+    if digest.len() != PREHASH_LEN {
+        return Err(format!("Prehash must be exactly {} bytes, got {}", PREHASH_LEN, digest.len()));
+    }
+    Ok(digest)
+}
+
+async fn sign_message(req: SignMessageRequest) -> ApiResult {
+    
+    let secret_bytes = match decode_secret(&req.secret) {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+ 
+    let keypair = match Keypair::from_bytes(&secret_bytes) {
+        Ok(kp) => kp,
+        Err(_) => return Ok(error_response("Invalid secret key")),
+    };
+    
+
+    let message_bytes = match message_bytes(&req.message, req.prehashed) {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    let signature = keypair.sign_message(&message_bytes);
+    
+    let encoded_signature = match req.signature_encoding.as_deref().unwrap_or("base64") {
+        "base64" => STANDARD.encode(signature.as_ref()),
+        "base58" => bs58::encode(signature.as_ref()).into_string(),
+        _ => return Ok(error_response("Unsupported signature encoding, expected \"base58\" or \"base64\"")),
+    };
+    
+    let response_data = SignatureData {
+        signature: encoded_signature,
+        signature_bytes: req.include_signature_bytes.then(|| signature.as_ref().to_vec()),
+        public_key: keypair.pubkey().to_string(),
+        message_len: message_bytes.len(),
+        message: req.message,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn sign_message_multi(req: MultiSignRequest) -> ApiResult {
+    
+    if req.secrets.is_empty() {
+        return Ok(error_response("At least one secret is required"));
+    }
+    
+    let encoding = req.signature_encoding.as_deref().unwrap_or("base64");
+    if encoding != "base64" && encoding != "base58" {
+        return Ok(error_response("Unsupported signature encoding, expected \"base58\" or \"base64\""));
+    }
+    
+    // Each secret is signed independently so one bad key doesn't fail the others
+    let signatures = req
+        .secrets
+        .iter()
+        .enumerate()
+        .map(|(index, secret)| {
+            let keypair = decode_secret(secret).and_then(|bytes| {
+                Keypair::from_bytes(&bytes).map_err(|_| "Invalid secret key".to_string())
+            });
+            match keypair {
+                Ok(keypair) => {
+                    let signature = keypair.sign_message(req.message.as_bytes());
+                    let encoded = if encoding == "base58" {
+                        bs58::encode(signature.as_ref()).into_string()
+                    } else {
+                        STANDARD.encode(signature.as_ref())
+                    };
+                    MultiSignatureEntry {
+                        index,
+                        public_key: Some(keypair.pubkey().to_string()),
+                        signature: Some(encoded),
+                        error: None,
+                    }
+                }
+                Err(message) => MultiSignatureEntry {
+                    index,
+                    public_key: None,
+                    signature: None,
+                    error: Some(message),
+                },
+            }
+        })
+        .collect();
+    
+    let response_data = MultiSignatureData {
+        message: req.message,
+        signatures,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+fn decode_verify_pubkey(req: &VerifyMessageRequest) -> Result<Pubkey, String> {
+    match req.pubkey_encoding.as_deref().unwrap_or("base58") {
+        "base58" => parse_pubkey(&req.pubkey, "Invalid public key"),
+        "hex" => {
+            let bytes = hex::decode(normalize_pubkey_input(&req.pubkey).trim_start_matches("0x"))
+                .map_err(|_| "Invalid public key, expected hex".to_string())?;
+            Pubkey::try_from(bytes.as_slice())
+                .map_err(|_| format!("Invalid public key, expected 32 bytes of hex but got {} bytes", bytes.len()))
+        }
+        _ => Err("Unsupported pubkey_encoding, expected base58 or hex".to_string()),
+    }
+}
+
+// Accepts base64 (as returned by /message/sign) or base58, and explains common length mistakes
+fn decode_signature(encoded: &str) -> Result<Signature, String> {
+    let decoded: Vec<Vec<u8>> = [STANDARD.decode(encoded).ok(), bs58::decode(encoded).into_vec().ok()]
+        .into_iter()
+        .flatten()
+        .collect();
+    
+    if let Some(bytes) = decoded.iter().find(|bytes| bytes.len() == 64) {
+        return Signature::try_from(bytes.as_slice()).map_err(|_| "Invalid signature".to_string());
+    }
+    if decoded.iter().any(|bytes| bytes.len() == 32) {
+        return Err("expected a 64-byte signature but got 32 bytes (did you pass a pubkey?)".to_string());
+    }
+    match decoded.first() {
+        Some(bytes) => Err(format!("expected a 64-byte signature but got {} bytes", bytes.len())),
+        // Without base64-only characters the input was most likely meant as base58
+        None => match base58_alphabet_error(encoded) {
+            Some(detail) if !encoded.contains(['+', '/', '=']) => {
+                Err(format!("Invalid signature format: {}", detail))
+            }
+            _ => Err("Invalid signature format".to_string()),
+        },
+    }
+}
+
+// Assembles the Sign-In-With-Solana message: header, address, optional statement, then the
+// advanced fields in spec order, each only when provided
+fn siws_message(req: &SiwsSignRequest) -> String {
+    let mut message = format!(
+        "{} wants you to sign in with your Solana account:\n{}",
+        req.domain, req.address
+    );
+    if let Some(statement) = &req.statement {
+        message.push_str("\n\n");
+        message.push_str(statement);
+    }
+    
+    let fields = [
+        ("URI", req.uri.as_deref()),
+        ("Version", req.version.as_deref()),
+        ("Chain ID", req.chain_id.as_deref()),
+        ("Nonce", Some(req.nonce.as_str())),
+        ("Issued At", Some(req.issued_at.as_str())),
+        ("Expiration Time", req.expiration_time.as_deref()),
+    ];
+    message.push('\n');
+    for (label, value) in fields {
+        if let Some(value) = value {
+            message.push_str(&format!("\n{}: {}", label, value));
+        }
+    }
+    message
+}
+
+async fn sign_siws_message(req: SiwsSignRequest) -> ApiResult {
+    
+    if req.domain.is_empty() || req.nonce.is_empty() || req.issued_at.is_empty() {
+        return Ok(error_response("domain, nonce and issued_at are required"));
+    }
+    
+    let address = match parse_pubkey(&req.address, "Invalid address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let secret_bytes = match decode_secret(&req.secret) {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let keypair = match Keypair::from_bytes(&secret_bytes) {
+        Ok(kp) => kp,
+        Err(_) => return Ok(error_response("Invalid secret key")),
+    };
+    
+    if keypair.pubkey() != address {
+        return Ok(error_response("address does not match the secret key"));
+    }
+    
+    let message = siws_message(&req);
+    let signature = keypair.sign_message(message.as_bytes());
+    
+    let response_data = SignatureData {
+        signature: STANDARD.encode(signature.as_ref()),
+        signature_bytes: req.include_signature_bytes.then(|| signature.as_ref().to_vec()),
+        public_key: keypair.pubkey().to_string(),
+        message_len: message.len(),
+        message,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn verify_message(req: VerifyMessageRequest) -> ApiResult {
+    
+    let pubkey = match decode_verify_pubkey(&req) {
+        Ok(pk) => pk,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    
+    let signature = match decode_signature(&req.signature) {
+        Ok(sig) => sig,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    
+    let message_bytes = match message_bytes(&req.message, req.prehashed) {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    let is_valid = signature.verify(&pubkey.to_bytes(), &message_bytes);
+    
+    let response_data = VerifyData {
+        valid: is_valid,
+        message: req.message,
+        pubkey: req.pubkey,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+// Lays out the Ed25519 precompile data like `new_ed25519_instruction`, but from an existing signature:
+// a count and padding byte, one offsets struct, then pubkey, signature and message inline
+async fn build_ed25519_verify_instruction(req: VerifyMessageRequest) -> ApiResult {
+    
+    let pubkey = match decode_verify_pubkey(&req) {
+        Ok(pk) => pk,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let signature = match decode_signature(&req.signature) {
+        Ok(sig) => sig,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let message_bytes = match message_bytes(&req.message, req.prehashed) {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    let message_offset = ed25519_instruction::DATA_START
+        + ed25519_instruction::PUBKEY_SERIALIZED_SIZE
+        + ed25519_instruction::SIGNATURE_SERIALIZED_SIZE;
+    if message_offset + message_bytes.len() > u16::MAX as usize {
+        return Ok(error_response("Message is too long for an Ed25519 instruction"));
+    }
+    if !signature.verify(&pubkey.to_bytes(), &message_bytes) {
+        return Ok(error_response("Signature does not verify against the public key and message"));
+    }
+    
+    let public_key_offset = ed25519_instruction::DATA_START as u16;
+    let signature_offset = public_key_offset + ed25519_instruction::PUBKEY_SERIALIZED_SIZE as u16;
+    // u16::MAX points each offset at this instruction's own data
+    let offsets = [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset as u16,
+        message_bytes.len() as u16,
+        u16::MAX,
+    ];
+    
+    let mut data = Vec::with_capacity(message_offset + message_bytes.len());
+    data.extend_from_slice(&[1, 0]);
+    for offset in offsets {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(&pubkey.to_bytes());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(&message_bytes);
+    
+    let response_data = InstructionData {
+        program_id: solana_sdk::ed25519_program::id().to_string(),
+        accounts: Vec::new(),
+        instruction_data: STANDARD.encode(&data),
+        ui_amount_string: None,
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data)))
+}
+
+// Builds the Secp256k1 precompile data for an Ethereum-style signature over keccak256(message).
+// The message is used as given, so personal_sign callers must include the "\x19Ethereum Signed Message" prefix.
+async fn build_secp256k1_verify_instruction(req: Secp256k1VerifyRequest) -> ApiResult {
+    
+    let eth_address = match hex::decode(req.eth_address.trim_start_matches("0x")) {
+        Ok(bytes) if bytes.len() == secp256k1_instruction::HASHED_PUBKEY_SERIALIZED_SIZE => bytes,
+        Ok(bytes) => return Ok(error_response(&format!("Ethereum address must be 20 bytes, got {}", bytes.len()))),
+        Err(_) => return Ok(error_response("Invalid Ethereum address, expected hex")),
+    };
+    
+    let signature = match hex::decode(req.signature.trim_start_matches("0x")) {
+        Ok(bytes) if bytes.len() == secp256k1_instruction::SIGNATURE_SERIALIZED_SIZE => bytes,
+        Ok(bytes) => return Ok(error_response(&format!("Signature must be 64 bytes (r || s), got {}", bytes.len()))),
+        Err(_) => return Ok(error_response("Invalid signature, expected hex")),
+    };
+    
+    // Ethereum encodes the recovery id as v = 27 or 28
+    let recovery_id = match req.recovery_id {
+        0..=3 => req.recovery_id,
+        27 | 28 => req.recovery_id - 27,
+        _ => return Ok(error_response("Recovery id must be 0-3, or 27/28 in Ethereum form")),
+    };
+    
+    let message_bytes = req.message.as_bytes();
+    let eth_address_offset = secp256k1_instruction::DATA_START;
+    let signature_offset = eth_address_offset + eth_address.len();
+    let message_offset = signature_offset + signature.len() + 1;
+    if message_offset + message_bytes.len() > u16::MAX as usize {
+        return Ok(error_response("Message is too long for a Secp256k1 instruction"));
+    }
+    
+    let offsets = secp256k1_instruction::SecpSignatureOffsets {
+        signature_offset: signature_offset as u16,
+        signature_instruction_index: req.instruction_index,
+        eth_address_offset: eth_address_offset as u16,
+        eth_address_instruction_index: req.instruction_index,
+        message_data_offset: message_offset as u16,
+        message_data_size: message_bytes.len() as u16,
+        message_instruction_index: req.instruction_index,
+    };
+    let offsets_bytes = match bincode::serialize(&offsets) {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(error_response("Failed to encode signature offsets")),
+    };
+    
+    let mut data = Vec::with_capacity(message_offset + message_bytes.len());
+    data.push(1);
+    data.extend_from_slice(&offsets_bytes);
+    data.extend_from_slice(&eth_address);
+    data.extend_from_slice(&signature);
+    data.push(recovery_id);
+    data.extend_from_slice(message_bytes);
+    
+    // Run the runtime's own check so an unrecoverable or mismatched signature fails here, not on-chain
+    let mut instruction_datas: Vec<&[u8]> = vec![&[]; req.instruction_index as usize + 1];
+    instruction_datas[req.instruction_index as usize] = &data;
+    if let Err(err) = secp256k1_instruction::verify(&data, &instruction_datas, &FeatureSet::all_enabled()) {
+        return Ok(error_response(&format!("Signature does not verify against the Ethereum address: {}", err)));
+    }
+    
+    let response_data = InstructionData {
+        program_id: solana_sdk::secp256k1_program::id().to_string(),
+        accounts: Vec::new(),
+        instruction_data: STANDARD.encode(&data),
+        ui_amount_string: None,
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data)))
+}
+
+
+async fn send_sol(req: SendSolRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+   
+    let from = match parse_pubkey(&req.from, "Invalid from address") {
         Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid mint address")),
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let to = match parse_pubkey(&req.to, "Invalid to address") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+
+    if req.lamports == 0 {
+        return Ok(error_response("Lamports amount must be greater than 0"));
+    }
+    
+  
+    let instruction = system_instruction::transfer(&from, &to, req.lamports);
+    
+    let response_data = SolTransferData {
+        program_id: solana_sdk::system_program::id().to_string(),
+        accounts: instruction.accounts.iter().map(|meta| meta.pubkey.to_string()).collect(),
+        instruction_data: STANDARD.encode(&instruction.data),
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data).with_compute_estimate(&estimate, SYSTEM_INSTRUCTION_CU)))
+}
+
+const MAX_SOL_RECIPIENTS: usize = 64;
+
+async fn send_sol_multi(req: SendSolMultiRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    if req.recipients.is_empty() {
+        return Ok(error_response("At least one recipient is required"));
+    }
+    if req.recipients.len() > MAX_SOL_RECIPIENTS {
+        return Ok(error_response(&format!("At most {} recipients are allowed per request", MAX_SOL_RECIPIENTS)));
+    }
+    
+    let mut errors = Vec::new();
+    let from = validate_pubkey(&req.from, "from", "Invalid from address", &mut errors);
+    
+    let mut recipients = Vec::with_capacity(req.recipients.len());
+    for (index, recipient) in req.recipients.iter().enumerate() {
+        let field = format!("recipients[{}]", index);
+        let Some(to) = validate_pubkey(&recipient.to, &format!("{}.to", field), "Invalid to address", &mut errors) else {
+            continue;
+        };
+        if recipients.iter().any(|(existing, _)| *existing == to) {
+            errors.push(FieldError {
+                field: format!("{}.to", field),
+                reason: "Duplicate recipient".to_string(),
+            });
+        }
+        if recipient.lamports == 0 {
+            errors.push(FieldError {
+                field: format!("{}.lamports", field),
+                reason: "Lamports amount must be greater than 0".to_string(),
+            });
+        }
+        recipients.push((to, recipient.lamports));
+    }
+    
+    let Some(from) = from else {
+        return Ok(validation_error_response(errors));
+    };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    let Some(total_lamports) = recipients
+        .iter()
+        .try_fold(0u64, |total, (_, lamports)| total.checked_add(*lamports))
+    else {
+        return Ok(error_response("Total lamports across recipients overflows u64"));
+    };
+    
+    let instructions = recipients
+        .iter()
+        .map(|(to, lamports)| {
+            let instruction = system_instruction::transfer(&from, to, *lamports);
+            SolTransferData {
+                program_id: solana_sdk::system_program::id().to_string(),
+                accounts: instruction.accounts.iter().map(|meta| meta.pubkey.to_string()).collect(),
+                instruction_data: STANDARD.encode(&instruction.data),
+            }
+        })
+        .collect::<Vec<_>>();
+    let compute_units = SYSTEM_INSTRUCTION_CU * instructions.len() as u32;
+    
+    let response_data = SolMultiTransferData {
+        instructions,
+        total_lamports,
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data).with_compute_estimate(&estimate, compute_units)))
+}
+
+async fn create_account(req: CreateAccountRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let payer = validate_pubkey(&req.payer, "payer", "Invalid payer address", &mut errors);
+    let new_account = validate_pubkey(&req.new_account, "new_account", "Invalid new account address", &mut errors);
+    let owner = validate_pubkey(&req.owner, "owner", "Invalid owner program id", &mut errors);
+    if req.space > system_instruction::MAX_PERMITTED_DATA_LENGTH {
+        errors.push(FieldError {
+            field: "space".to_string(),
+            reason: format!("Account size cannot exceed {} bytes", system_instruction::MAX_PERMITTED_DATA_LENGTH),
+        });
+    }
+    
+    let (Some(payer), Some(new_account), Some(owner)) = (payer, new_account, owner) else {
+        return Ok(validation_error_response(errors));
+    };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    if payer == new_account {
+        return Ok(error_response("Payer and new account must be different accounts"));
+    }
+    
+    let rent_exempt_lamports = Rent::default().minimum_balance(req.space as usize);
+    let lamports = req.lamports.unwrap_or(rent_exempt_lamports);
+    let mut warnings = Vec::new();
+    if lamports < rent_exempt_lamports {
+        warnings.push(format!(
+            "{} lamports is below the rent-exempt minimum of {} for {} bytes",
+            lamports, rent_exempt_lamports, req.space
+        ));
+    }
+    
+    // The new account signs too, proving the caller holds the keypair for the address being created
+    let instruction = system_instruction::create_account(&payer, &new_account, lamports, req.space, &owner);
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: instruction_accounts(&instruction),
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: None,
+    };
+    
+    Ok(success_response_with_warnings(
+        with_blockhash_hint(response_data).with_compute_estimate(&estimate, SYSTEM_INSTRUCTION_CU),
+        warnings,
+    ))
+}
+
+// For accounts that were funded with a plain transfer: size the data, then hand ownership to a program
+async fn allocate_assign(req: AllocateAssignRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let account = validate_pubkey(&req.account, "account", "Invalid account address", &mut errors);
+    let owner = validate_pubkey(&req.owner, "owner", "Invalid owner program id", &mut errors);
+    if req.space > system_instruction::MAX_PERMITTED_DATA_LENGTH {
+        errors.push(FieldError {
+            field: "space".to_string(),
+            reason: format!("Account size cannot exceed {} bytes", system_instruction::MAX_PERMITTED_DATA_LENGTH),
+        });
+    }
+    
+    let (Some(account), Some(owner)) = (account, owner) else {
+        return Ok(validation_error_response(errors));
+    };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    // allocate has to come first: once assigned away from the system program the account can't be resized
+    let allocate = system_instruction::allocate(&account, req.space);
+    let assign = system_instruction::assign(&account, &owner);
+    
+    let response_data = InstructionSequenceData {
+        instructions: [allocate, assign]
+            .iter()
+            .map(|instruction| InstructionData {
+                program_id: instruction.program_id.to_string(),
+                accounts: instruction_accounts(instruction),
+                instruction_data: STANDARD.encode(&instruction.data),
+                ui_amount_string: None,
+            })
+            .collect(),
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data).with_compute_estimate(&estimate, 2 * SYSTEM_INSTRUCTION_CU)))
+}
+
+async fn withdraw_nonce(req: WithdrawNonceRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let nonce_account = validate_pubkey(&req.nonce_account, "nonce_account", "Invalid nonce account address", &mut errors);
+    let authority = validate_pubkey(&req.authority, "authority", "Invalid nonce authority address", &mut errors);
+    let destination = validate_pubkey(&req.destination, "destination", "Invalid destination address", &mut errors);
+    if req.lamports == 0 {
+        errors.push(FieldError {
+            field: "lamports".to_string(),
+            reason: "Amount must be greater than 0".to_string(),
+        });
+    }
+    
+    let (Some(nonce_account), Some(authority), Some(destination)) = (nonce_account, authority, destination) else {
+        return Ok(validation_error_response(errors));
+    };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    // The runtime only allows a partial withdrawal that leaves the account rent-exempt, or draining it entirely
+    let instruction = system_instruction::withdraw_nonce_account(&nonce_account, &authority, &destination, req.lamports);
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: instruction_accounts(&instruction),
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: None,
     };
     
-    let destination = match Pubkey::from_str(&req.destination) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid destination address")),
+    Ok(success_response(with_blockhash_hint(response_data).with_compute_estimate(&estimate, SYSTEM_INSTRUCTION_CU)))
+}
+
+
+async fn send_token(req: SendTokenRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+   
+    let mut errors = Vec::new();
+    let destination = validate_pubkey(&req.destination, "destination", "Invalid destination address", &mut errors);
+    let mint = validate_pubkey(&req.mint, "mint", "Invalid mint address", &mut errors);
+    let owner = validate_pubkey(&req.owner, "owner", "Invalid owner address", &mut errors);
+    let token_program = match req.program_id.as_deref() {
+        Some(program_id) => validate_pubkey(program_id, "program_id", "Invalid program id", &mut errors),
+        None => Some(spl_token::id()),
     };
     
-    let authority = match Pubkey::from_str(&req.authority) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid authority address")),
+    let multisig_authority = req
+        .multisig_authority
+        .as_deref()
+        .and_then(|multisig| validate_pubkey(multisig, "multisig_authority", "Invalid multisig authority address", &mut errors));
+    let signer_pubkeys: Vec<Pubkey> = req
+        .signer_pubkeys
+        .iter()
+        .enumerate()
+        .filter_map(|(index, signer)| {
+            validate_pubkey(signer, &format!("signer_pubkeys[{}]", index), "Invalid signer address", &mut errors)
+        })
+        .collect();
+    match (req.multisig_authority.is_some(), req.signer_pubkeys.len()) {
+        (true, 0) => errors.push(FieldError {
+            field: "signer_pubkeys".to_string(),
+            reason: "signer_pubkeys is required with multisig_authority".to_string(),
+        }),
+        (true, count) if count > spl_token::instruction::MAX_SIGNERS => errors.push(FieldError {
+            field: "signer_pubkeys".to_string(),
+            reason: format!("A multisig has at most {} signers", spl_token::instruction::MAX_SIGNERS),
+        }),
+        (false, count) if count > 0 => errors.push(FieldError {
+            field: "signer_pubkeys".to_string(),
+            reason: "signer_pubkeys is only used with multisig_authority".to_string(),
+        }),
+        _ => {}
+    }
+    
+    if req.amount == 0 {
+        errors.push(FieldError {
+            field: "amount".to_string(),
+            reason: "Amount must be greater than 0".to_string(),
+        });
+    }
+    
+    // A wrong decimals value makes transfer_checked fail on-chain, but only the caller can look it up
+    match (req.checked, req.decimals, req.confirm_decimals) {
+        (true, None, _) => errors.push(FieldError {
+            field: "decimals".to_string(),
+            reason: "decimals is required for a checked transfer".to_string(),
+        }),
+        (true, Some(_), false) => errors.push(FieldError {
+            field: "confirm_decimals".to_string(),
+            reason: "Set confirm_decimals to true after verifying decimals against the mint".to_string(),
+        }),
+        (false, _, true) => errors.push(FieldError {
+            field: "confirm_decimals".to_string(),
+            reason: "confirm_decimals is only used with checked".to_string(),
+        }),
+        _ => {}
+    }
+    
+    let (Some(destination), Some(mint), Some(owner), Some(token_program)) = (destination, mint, owner, token_program) else {
+        return Ok(validation_error_response(errors));
     };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    if req.dry_run {
+        return Ok(success_response(ValidationData { valid: true }));
+    }
     
   
-    let instruction = match token_instruction::mint_to(
-        &spl_token::id(),
-        &mint,
-        &destination,
-        &authority,
-        &[],
-        req.amount,
-    ) {
-        Ok(instruction) => instruction,
-        Err(_) => return Ok(error_response("Failed to create mint instruction")),
+    let authority = multisig_authority.unwrap_or(owner);
+    let source_ata = associated_token_address(&authority, &mint, &token_program);
+    let dest_ata = associated_token_address(&destination, &mint, &token_program);
+    
+    // With a multisig authority the individual signers sign instead of the multisig account itself
+    let signer_refs: Vec<&Pubkey> = signer_pubkeys.iter().collect();
+    let instruction = match req.decimals.filter(|_| req.checked) {
+        Some(decimals) => token_instruction::transfer_checked(
+            &spl_token::id(),
+            &source_ata,
+            &mint,
+            &dest_ata,
+            &authority,
+            &signer_refs,
+            req.amount,
+            decimals,
+        ),
+        None => token_instruction::transfer(
+            &spl_token::id(),
+            &source_ata,
+            &dest_ata,
+            &authority,
+            &signer_refs,
+            req.amount,
+        ),
+    };
+    let instruction = match instruction {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create transfer instruction", error)),
     };
     
-    let response_data = InstructionData {
-        program_id: spl_token::id().to_string(),
+    let mut warnings = Vec::new();
+    if !req.checked {
+        warnings.push("transfer does not check decimals; use checked to avoid a decimal mismatch".to_string());
+    }
+    
+    if req.create_source_ata {
+        let create_ata = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &owner,
+            &authority,
+            &mint,
+            &token_program,
+        );
+        warnings.push(format!(
+            "Source token account {} is created empty; it must be funded with the transfer amount before this transfer",
+            source_ata
+        ));
+        
+        let response_data = InstructionSequenceData {
+            instructions: vec![
+                InstructionData {
+                    program_id: create_ata.program_id.to_string(),
+                    accounts: instruction_accounts(&create_ata),
+                    instruction_data: STANDARD.encode(&create_ata.data),
+                    ui_amount_string: None,
+                },
+                InstructionData {
+                    program_id: instruction.program_id.to_string(),
+                    accounts: instruction_accounts(&instruction),
+                    instruction_data: STANDARD.encode(&instruction.data),
+                    ui_amount_string: req.decimals.map(|decimals| format_ui_amount(req.amount, decimals)),
+                },
+            ],
+        };
+        return Ok(success_response_with_warnings(
+            with_blockhash_hint(response_data).with_compute_estimate(&estimate, CREATE_ATA_CU + TOKEN_TRANSFER_CU),
+            warnings,
+        ));
+    }
+    
+    // The server is offline, so it cannot check that the derived source account exists
+    warnings.push(format!(
+        "Source token account {} is derived from {} and mint; it must already exist and hold the transfer amount",
+        source_ata,
+        if multisig_authority.is_some() { "multisig_authority" } else { "owner" }
+    ));
+    
+    // The isSigner-only account list can't express a read-only multisig account or the mint that
+    // transfer_checked reads, so the full metas are returned
+    if multisig_authority.is_some() || req.checked {
+        let response_data = InstructionData {
+            program_id: instruction.program_id.to_string(),
+            accounts: instruction_accounts(&instruction),
+            instruction_data: STANDARD.encode(&instruction.data),
+            ui_amount_string: req.decimals.map(|decimals| format_ui_amount(req.amount, decimals)),
+        };
+        return Ok(success_response_with_warnings(
+            with_blockhash_hint(response_data).with_compute_estimate(&estimate, TOKEN_TRANSFER_CU),
+            warnings,
+        ));
+    }
+    
+    let response_data = TokenTransferData {
+        program_id: instruction.program_id.to_string(),
         accounts: vec![
-            AccountInfo {
-                pubkey: mint.to_string(),
+            TokenAccountInfo {
+                pubkey: source_ata.to_string(),
                 is_signer: false,
-                is_writable: true,
             },
-            AccountInfo {
-                pubkey: destination.to_string(),
+            TokenAccountInfo {
+                pubkey: dest_ata.to_string(),
                 is_signer: false,
-                is_writable: true,
             },
-            AccountInfo {
-                pubkey: authority.to_string(),
+            TokenAccountInfo {
+                pubkey: owner.to_string(),
                 is_signer: true,
-                is_writable: false,
             },
         ],
         instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: req.decimals.map(|decimals| format_ui_amount(req.amount, decimals)),
+    };
+    
+    Ok(success_response_with_warnings(with_blockhash_hint(response_data).with_compute_estimate(&estimate, TOKEN_TRANSFER_CU), warnings))
+}
+
+async fn send_token_with_ata(req: SendTokenWithAtaRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    let mut errors = Vec::new();
+    let destination = validate_pubkey(&req.destination, "destination", "Invalid destination address", &mut errors);
+    let mint = validate_pubkey(&req.mint, "mint", "Invalid mint address", &mut errors);
+    let owner = validate_pubkey(&req.owner, "owner", "Invalid owner address", &mut errors);
+    let payer = validate_pubkey(&req.payer, "payer", "Invalid payer address", &mut errors);
+    let token_program = match req.program_id.as_deref() {
+        Some(program_id) => validate_pubkey(program_id, "program_id", "Invalid program id", &mut errors),
+        None => Some(spl_token::id()),
+    };
+    
+    if req.amount == 0 {
+        errors.push(FieldError {
+            field: "amount".to_string(),
+            reason: "Amount must be greater than 0".to_string(),
+        });
+    }
+    
+    let (Some(destination), Some(mint), Some(owner), Some(payer), Some(token_program)) =
+        (destination, mint, owner, payer, token_program)
+    else {
+        return Ok(validation_error_response(errors));
+    };
+    if !errors.is_empty() {
+        return Ok(validation_error_response(errors));
+    }
+    
+    let source_ata = associated_token_address(&owner, &mint, &token_program);
+    let dest_ata = associated_token_address(&destination, &mint, &token_program);
+    
+    // The idempotent variant succeeds when the ATA already exists, so the pair is safe to submit unconditionally
+    let create_ata = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        &payer,
+        &destination,
+        &mint,
+        &token_program,
+    );
+    
+    let transfer = match token_instruction::transfer(
+        &spl_token::id(),
+        &source_ata,
+        &dest_ata,
+        &owner,
+        &[],
+        req.amount,
+    ) {
+        Ok(instruction) => for_token_program(instruction, token_program),
+        Err(error) => return Ok(program_error_response("Failed to create transfer instruction", error)),
+    };
+    
+    let response_data = InstructionSequenceData {
+        instructions: vec![
+            InstructionData {
+                program_id: create_ata.program_id.to_string(),
+                accounts: instruction_accounts(&create_ata),
+                instruction_data: STANDARD.encode(&create_ata.data),
+                ui_amount_string: None,
+            },
+            InstructionData {
+                program_id: transfer.program_id.to_string(),
+                accounts: instruction_accounts(&transfer),
+                instruction_data: STANDARD.encode(&transfer.data),
+                ui_amount_string: req.decimals.map(|decimals| format_ui_amount(req.amount, decimals)),
+            },
+        ],
+    };
+    
+    Ok(success_response(
+        with_blockhash_hint(response_data).with_compute_estimate(&estimate, CREATE_ATA_CU + TOKEN_TRANSFER_CU),
+    ))
+}
+
+
+async fn health(stats: Arc<ServerStats>, base_path: String) -> ApiResult {
+    let response_data = HealthData {
+        status: "ok".to_string(),
+        base_path,
+        requests_served: stats.requests_served.load(Ordering::Relaxed),
+        uptime_seconds: stats.started_at.elapsed().as_secs(),
+        derivation_cache_hits: derivation_cache().hits.load(Ordering::Relaxed),
+        derivation_cache_misses: derivation_cache().misses.load(Ordering::Relaxed),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn program_ids() -> ApiResult {
+    let response_data = ProgramIdsData {
+        system_program: solana_sdk::system_program::id().to_string(),
+        token_program: spl_token::id().to_string(),
+        token_2022_program: spl_token_2022::id().to_string(),
+        associated_token_program: spl_associated_token_account::id().to_string(),
+        memo_program: spl_memo::id().to_string(),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn limits() -> ApiResult {
+    let response_data = LimitsData {
+        max_body_bytes: max_body_bytes(),
+        max_batch_operations: max_batch_operations(),
+        max_mint_recipients: MAX_MINT_RECIPIENTS,
+        max_sol_recipients: MAX_SOL_RECIPIENTS,
+        max_pda_seeds: solana_sdk::pubkey::MAX_SEEDS,
+        max_pda_seed_bytes: solana_sdk::pubkey::MAX_SEED_LEN,
+        max_memo_bytes: MAX_MEMO_BYTES,
+        max_transaction_bytes: PACKET_DATA_SIZE,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+// Dependency versions are read from Cargo.lock by build.rs
+async fn version() -> ApiResult {
+    let response_data = VersionData {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        solana_sdk: env!("SOLANA_SDK_VERSION").to_string(),
+        spl_token: env!("SPL_TOKEN_VERSION").to_string(),
+        spl_token_2022: env!("SPL_TOKEN_2022_VERSION").to_string(),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+// Seed of the demo keypair behind /examples; it is public, so never fund it
+const EXAMPLE_SEED: [u8; 32] = [7; 32];
+const EXAMPLE_MESSAGE: &str = "Hello, Solana!";
+// An Ethereum key signing EXAMPLE_MESSAGE, for the secp256k1 example; there is no secp256k1 signer here
+const EXAMPLE_ETH_ADDRESS: &str = "0x4a62316623ad457f02cdc5d997ded67a383ec569";
+const EXAMPLE_ETH_SIGNATURE: &str = "338d3089720a11971e00b09a80c892cdfaf3ae498f87871a9318948592e28db67e9cc25c9706b6613d3c20794450e18f7fabba2a2f973fd5e2db3ce146a6a85f";
+const EXAMPLE_ETH_RECOVERY_ID: u8 = 1;
+
+// One well-formed body per JSON endpoint, keyed by path below BASE_PATH. Secrets, signatures and
+// transactions all come from the EXAMPLE_SEED keypair, so each body can be posted back unchanged.
+async fn examples() -> ApiResult {
+    let Ok(demo) = keypair_from_seed(&EXAMPLE_SEED) else {
+        return Ok(error_response_with_status(
+            "Failed to build the example keypair",
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    };
+    let owner = demo.pubkey().to_string();
+    let recipient = "CVCrWfhYPLd2s5FEGAorR53K6U1ZzDP1LqRcX5mcMVWK";
+    let mint = "sJDxkf4T6ZX8PtZ8DDXvX7nNot6vfFWGpHw8g6YFyZF";
+    let token_account = "9wg5bXMFWeN9EZjayhbMKYtuaWqYNmMk8kHia3HGD8FP";
+    let secret = demo.to_base58_string();
+    let signature = STANDARD.encode(demo.sign_message(EXAMPLE_MESSAGE.as_bytes()).as_ref());
+    let recent_blockhash = "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N";
+    
+    let transfer = system_instruction::transfer(&demo.pubkey(), &Pubkey::from_str(recipient).unwrap_or_default(), 1_000_000);
+    let transfer_instruction = json!({
+        "program_id": transfer.program_id.to_string(),
+        "accounts": instruction_accounts(&transfer),
+        "instruction_data": STANDARD.encode(&transfer.data),
+    });
+    let mut message = Message::new(&[transfer], Some(&demo.pubkey()));
+    message.recent_blockhash = Hash::from_str(recent_blockhash).unwrap_or_default();
+    let transfer_message = STANDARD.encode(message.serialize());
+    let mut transaction = solana_sdk::transaction::Transaction::new_unsigned(message);
+    transaction.sign(&[&demo], transaction.message.recent_blockhash);
+    let Ok(transfer_transaction) = bincode::serialize(&transaction).map(|bytes| STANDARD.encode(bytes)) else {
+        return Ok(error_response_with_status(
+            "Failed to build the example transaction",
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    };
+    
+    let response_data = json!({
+        "/keypair/derive": {
+            "seed": hex::encode(EXAMPLE_SEED),
+            "path": [
+                { "index": 44, "hardened": true },
+                { "index": 501, "hardened": true },
+                { "index": 0, "hardened": true },
+                { "index": 0, "hardened": true },
+            ],
+        },
+        "/keypair/from-file-format": { "keyfile": demo.to_bytes().to_vec() },
+        "/keypair/inspect": { "secret": secret, "encoding": "base58" },
+        "/keypair/verify-pair": { "pubkey": owner, "secret": secret },
+        "/keypair/sign-challenge": { "challenge": "prove-possession-123", "include_secret": false },
+        "/pubkey/normalize": { "pubkey": format!("  {} ", owner) },
+        "/pubkey/encodings": { "pubkey": owner },
+        "/address/derive": { "owner": owner, "mint": mint, "token_program": spl_token_2022::id().to_string() },
+        "/token/create": { "mintAuthority": owner, "mint": mint, "decimals": 6 },
+        "/token/mint": { "mint": mint, "destination": token_account, "authority": owner, "amount": 1000000 },
+        "/token/mint/batch": {
+            "mint": mint,
+            "authority": owner,
+            "recipients": [{ "destination": token_account, "amount": 1000000 }],
+        },
+        "/token/mint-checked": { "mint": mint, "destination": token_account, "authority": owner, "amount": 1000000, "decimals": 6 },
+        "/token/burn-checked": { "account": token_account, "mint": mint, "authority": owner, "amount": 1000000, "decimals": 6 },
+        "/token/close-with-transfer": {
+            "account": token_account,
+            "owner": owner,
+            "destination": "87osBNHFKR8t5rkQA3tTsZXo73hp2eeZxqhRN6pz1CLF",
+            "amount": 250000,
+        },
+        "/token/init-multisig": {
+            "account": "7H3EFH6WbwifUhGSRSHEbKtEExNBjwWRgMjZJjm2t8jV",
+            "signers": [owner, recipient],
+            "m": 2,
+        },
+        "/token/sync-native": { "account": token_account },
+        "/token/wrap-sol": { "owner": owner, "payer": owner, "lamports": 1000000000u64 },
+        "/token/decode-instruction": { "data": "DkBCDwAAAAAABg==" },
+        "/token/mint-size": { "program_id": spl_token_2022::id().to_string(), "extensions": ["mint_close_authority"] },
+        "/message/sign": { "message": EXAMPLE_MESSAGE, "secret": secret },
+        "/message/sign-siws": {
+            "domain": "example.com",
+            "address": owner,
+            "statement": "Sign in to Example",
+            "nonce": "32891756",
+            "issued_at": "2024-01-01T00:00:00Z",
+            "secret": secret,
+        },
+        "/message/verify": { "message": EXAMPLE_MESSAGE, "signature": signature, "pubkey": owner },
+        "/sign/multi": { "message": EXAMPLE_MESSAGE, "secrets": [secret, secret] },
+        "/ed25519/build-verify-ix": { "message": EXAMPLE_MESSAGE, "signature": signature, "pubkey": owner },
+        "/secp256k1/build-verify-ix": {
+            "eth_address": EXAMPLE_ETH_ADDRESS,
+            "message": EXAMPLE_MESSAGE,
+            "signature": EXAMPLE_ETH_SIGNATURE,
+            "recovery_id": EXAMPLE_ETH_RECOVERY_ID,
+        },
+        "/send/sol": { "from": owner, "to": recipient, "lamports": 100000000 },
+        "/send/sol/multi": { "from": owner, "recipients": [{ "to": recipient, "lamports": 100000000 }] },
+        "/account/create": {
+            "payer": owner,
+            "new_account": "7H3EFH6WbwifUhGSRSHEbKtEExNBjwWRgMjZJjm2t8jV",
+            "owner": solana_sdk::system_program::id().to_string(),
+            "space": 0,
+        },
+        "/account/allocate-assign": {
+            "account": "7H3EFH6WbwifUhGSRSHEbKtEExNBjwWRgMjZJjm2t8jV",
+            "space": 165,
+            "owner": spl_token::id().to_string(),
+        },
+        "/nonce/withdraw": {
+            "nonce_account": "7H3EFH6WbwifUhGSRSHEbKtEExNBjwWRgMjZJjm2t8jV",
+            "authority": owner,
+            "destination": recipient,
+            "lamports": 1447680,
+        },
+        "/send/token": { "destination": recipient, "mint": mint, "owner": owner, "amount": 1000000 },
+        "/send/token/with-ata": { "destination": recipient, "mint": mint, "owner": owner, "payer": owner, "amount": 1000000 },
+        "/batch": {
+            "operations": [
+                { "type": "send_sol", "from": owner, "to": recipient, "lamports": 100000000 },
+                { "type": "mint_token", "mint": mint, "destination": token_account, "authority": owner, "amount": 1000000 },
+            ],
+        },
+        "/cost/breakdown": {
+            "fee_payer": owner,
+            "operations": [
+                { "type": "create_token", "mintAuthority": owner, "mint": mint, "decimals": 6, "include_create_account": true, "payer": owner },
+            ],
+        },
+        "/convert/lamports-to-sol": { "lamports": 1250000000u64 },
+        "/rent/minimum": { "space": 165 },
+        "/instruction/encode": {
+            "program_id": solana_sdk::system_program::id().to_string(),
+            "accounts": transfer_instruction["accounts"],
+            "data": "AgAAAEBCDwAAAAAA",
+        },
+        "/transaction/build": { "fee_payer": owner, "recent_blockhash": recent_blockhash, "instructions": [transfer_instruction] },
+        "/transaction/size": { "fee_payer": owner, "recent_blockhash": recent_blockhash, "instructions": [transfer_instruction] },
+        "/transaction/required-signers": { "message": transfer_message, "pubkey": owner },
+        "/transaction/decode": { "transaction": transfer_transaction },
+        "/transaction/partial-sign": { "transaction": transfer_transaction, "secret": secret },
+        "/transaction/verify-signature": { "transaction": transfer_transaction, "signature_index": 0 },
+    });
+    
+    Ok(success_response(response_data))
+}
+
+// Each operation goes through its regular handler, so the per-operation result is exactly the
+// envelope the single endpoint would have returned
+async fn reply_to_json(reply: Box<dyn warp::Reply>) -> serde_json::Value {
+    let body = warp::Reply::into_response(reply).into_body();
+    match warp::hyper::body::to_bytes(body).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null),
+        Err(_) => serde_json::Value::Null,
+    }
+}
+
+async fn batch(req: BatchRequest, estimate: ComputeEstimateQuery) -> ApiResult {
+    
+    if req.operations.is_empty() {
+        return Ok(error_response("At least one operation is required"));
+    }
+    if req.operations.len() > max_batch_operations() {
+        return Ok(error_response(&format!(
+            "Batch has {} operations; at most {} are allowed per request",
+            req.operations.len(),
+            max_batch_operations()
+        )));
+    }
+    
+    let mut results = Vec::with_capacity(req.operations.len());
+    let mut total_lamports = Some(0u64);
+    let mut total_token_amount = Some(0u64);
+    
+    let cancellation = cancellation();
+    for operation in req.operations {
+        if cancellation.is_cancelled() {
+            return Ok(deadline_exceeded_response());
+        }
+        let (reply, lamports, token_amount) = match operation {
+            BatchOperation::CreateToken(op) => (create_token(op, estimate).await?, 0, 0),
+            BatchOperation::MintToken(op) => {
+                let amount = op.amount;
+                (mint_token(op, estimate).await?, 0, amount)
+            }
+            BatchOperation::SendSol(op) => {
+                let lamports = op.lamports;
+                (send_sol(op, estimate).await?, lamports, 0)
+            }
+            BatchOperation::SendToken(op) => {
+                let amount = op.amount;
+                (send_token(op, estimate).await?, 0, amount)
+            }
+        };
+        
+        let result = reply_to_json(reply).await;
+        // Only operations that produced an instruction count towards the totals
+        if result["success"] == serde_json::Value::Bool(true) {
+            total_lamports = total_lamports.and_then(|total| total.checked_add(lamports));
+            total_token_amount = total_token_amount.and_then(|total| total.checked_add(token_amount));
+        }
+        results.push(result);
+    }
+    
+    let mut warnings = Vec::new();
+    if total_lamports.is_none() {
+        warnings.push("Total lamports across operations overflows u64".to_string());
+    }
+    if total_token_amount.is_none() {
+        warnings.push("Total token amount across operations overflows u64".to_string());
+    }
+    
+    let response_data = BatchData {
+        results,
+        total_lamports,
+        total_token_amount,
+    };
+    
+    Ok(success_response_with_warnings(response_data, warnings))
+}
+
+// The cluster's base fee per signature; priority fees are not included
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+// Handlers return either a single instruction or an `instructions` sequence
+fn reply_instructions(data: &serde_json::Value) -> Vec<&serde_json::Value> {
+    match data["instructions"].as_array() {
+        Some(instructions) => instructions.iter().collect(),
+        None => vec![data],
+    }
+}
+
+// Rent funded by a system create_account instruction, zero for anything else
+fn create_account_lamports(instruction: &serde_json::Value) -> u64 {
+    if instruction["program_id"] != solana_sdk::system_program::id().to_string().as_str() {
+        return 0;
+    }
+    let data = STANDARD.decode(instruction["instruction_data"].as_str().unwrap_or_default()).unwrap_or_default();
+    match bincode::deserialize(&data) {
+        Ok(system_instruction::SystemInstruction::CreateAccount { lamports, .. }) => lamports,
+        _ => 0,
+    }
+}
+
+// Handlers serialize accounts three ways: full metas (`is_signer`), the /send/token shape (`isSigner`),
+// and /send/sol's bare pubkeys, whose signer comes from rebuilding the transfer they encode
+fn instruction_signers(instruction: &serde_json::Value) -> Vec<String> {
+    let accounts = instruction["accounts"].as_array().map(Vec::as_slice).unwrap_or_default();
+    if accounts.iter().all(serde_json::Value::is_string) {
+        let data = STANDARD.decode(instruction["instruction_data"].as_str().unwrap_or_default()).unwrap_or_default();
+        let pubkeys: Vec<Pubkey> = accounts
+            .iter()
+            .filter_map(|account| Pubkey::from_str(account.as_str().unwrap_or_default()).ok())
+            .collect();
+        return match (bincode::deserialize(&data), pubkeys.as_slice()) {
+            (Ok(system_instruction::SystemInstruction::Transfer { lamports }), [from, to]) => {
+                system_instruction::transfer(from, to, lamports)
+                    .accounts
+                    .iter()
+                    .filter(|meta| meta.is_signer)
+                    .map(|meta| meta.pubkey.to_string())
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+    }
+    accounts
+        .iter()
+        .filter(|account| account["is_signer"] == serde_json::Value::Bool(true) || account["isSigner"] == serde_json::Value::Bool(true))
+        .map(|account| account["pubkey"].as_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+async fn cost_breakdown(req: CostBreakdownRequest) -> ApiResult {
+    
+    if req.operations.is_empty() {
+        return Ok(error_response("At least one operation is required"));
+    }
+    if req.operations.len() > max_batch_operations() {
+        return Ok(error_response(&format!(
+            "Batch has {} operations; at most {} are allowed per request",
+            req.operations.len(),
+            max_batch_operations()
+        )));
+    }
+    
+    let mut signers = Vec::new();
+    if let Some(fee_payer) = &req.fee_payer {
+        match parse_pubkey(fee_payer, "Invalid fee payer address") {
+            Ok(pubkey) => signers.push(pubkey.to_string()),
+            Err(message) => return Ok(error_response(&message)),
+        }
+    }
+    
+    let estimate = ComputeEstimateQuery::default();
+    let mut rent_lamports = 0u64;
+    for (index, operation) in req.operations.into_iter().enumerate() {
+        let reply = match operation {
+            BatchOperation::CreateToken(op) => create_token(op, estimate).await?,
+            BatchOperation::MintToken(op) => mint_token(op, estimate).await?,
+            BatchOperation::SendSol(op) => send_sol(op, estimate).await?,
+            BatchOperation::SendToken(op) => send_token(op, estimate).await?,
+        };
+        
+        let result = reply_to_json(reply).await;
+        if result["success"] != serde_json::Value::Bool(true) {
+            let message = result["error"].as_str().unwrap_or("Invalid operation");
+            return Ok(error_response(&format!("Operation {}: {}", index, message)));
+        }
+        
+        // Each distinct signer pays for one signature, however many instructions it signs
+        for instruction in reply_instructions(&result["data"]) {
+            rent_lamports = rent_lamports.saturating_add(create_account_lamports(instruction));
+            for pubkey in instruction_signers(instruction) {
+                if !signers.contains(&pubkey) {
+                    signers.push(pubkey);
+                }
+            }
+        }
+    }
+    
+    let signature_fee_lamports = LAMPORTS_PER_SIGNATURE * signers.len() as u64;
+    let total_lamports = signature_fee_lamports.saturating_add(rent_lamports);
+    
+    let response_data = CostBreakdownData {
+        signers,
+        signature_fee_lamports,
+        rent_lamports,
+        total_lamports,
+        total_sol: format_ui_amount(total_lamports, 9),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn rent_minimum(req: RentMinimumRequest) -> ApiResult {
+    
+    if req.space > system_instruction::MAX_PERMITTED_DATA_LENGTH {
+        return Ok(error_response(&format!(
+            "Account size cannot exceed {} bytes",
+            system_instruction::MAX_PERMITTED_DATA_LENGTH
+        )));
+    }
+    
+    let response_data = RentData {
+        space: req.space,
+        lamports: Rent::default().minimum_balance(req.space as usize),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+// Fixed-size mint extensions; variable-length ones like token metadata depend on their contents
+fn mint_extension_type(name: &str) -> Option<ExtensionType> {
+    match name {
+        "transfer_fee_config" => Some(ExtensionType::TransferFeeConfig),
+        "mint_close_authority" => Some(ExtensionType::MintCloseAuthority),
+        "confidential_transfer_mint" => Some(ExtensionType::ConfidentialTransferMint),
+        "confidential_transfer_fee_config" => Some(ExtensionType::ConfidentialTransferFeeConfig),
+        "default_account_state" => Some(ExtensionType::DefaultAccountState),
+        "non_transferable" => Some(ExtensionType::NonTransferable),
+        "interest_bearing_config" => Some(ExtensionType::InterestBearingConfig),
+        "permanent_delegate" => Some(ExtensionType::PermanentDelegate),
+        "transfer_hook" => Some(ExtensionType::TransferHook),
+        "metadata_pointer" => Some(ExtensionType::MetadataPointer),
+        "group_pointer" => Some(ExtensionType::GroupPointer),
+        "group_member_pointer" => Some(ExtensionType::GroupMemberPointer),
+        _ => None,
+    }
+}
+
+async fn mint_size(req: MintSizeRequest) -> ApiResult {
+    
+    let program_id = match resolve_program_id(req.program_id.as_deref(), spl_token::id()) {
+        Ok(program_id) => program_id,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let space = if program_id == spl_token_2022::id() {
+        let mut extension_types = Vec::with_capacity(req.extensions.len());
+        for name in &req.extensions {
+            match mint_extension_type(name) {
+                Some(extension_type) => extension_types.push(extension_type),
+                None => return Ok(error_response(&format!("Unsupported mint extension: {}", name))),
+            }
+        }
+        match ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extension_types) {
+            Ok(space) => space,
+            Err(_) => return Ok(error_response("Failed to calculate mint size")),
+        }
+    } else if req.extensions.is_empty() {
+        spl_token::state::Mint::LEN
+    } else {
+        return Ok(error_response("Extensions require the Token-2022 program id"));
+    };
+    
+    let response_data = MintSizeData {
+        program_id: program_id.to_string(),
+        space: space as u64,
+        rent_lamports: Rent::default().minimum_balance(space),
+        extensions: req.extensions,
+    };
+    
+    Ok(success_response(response_data))
+}
+
+// Rent for a new SPL token mint plus one associated token account, using the default rent parameters
+async fn token_setup_cost() -> ApiResult {
+    let rent = Rent::default();
+    let accounts = vec![
+        AccountRentData {
+            account: "mint".to_string(),
+            space: spl_token::state::Mint::LEN as u64,
+            lamports: rent.minimum_balance(spl_token::state::Mint::LEN),
+        },
+        AccountRentData {
+            account: "associated_token_account".to_string(),
+            space: spl_token::state::Account::LEN as u64,
+            lamports: rent.minimum_balance(spl_token::state::Account::LEN),
+        },
+    ];
+    let total_lamports = accounts.iter().map(|account| account.lamports).sum();
+    
+    let response_data = TokenSetupCostData {
+        accounts,
+        total_lamports,
+        total_sol: format_ui_amount(total_lamports, 9),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+async fn lamports_to_sol(req: LamportsToSolRequest) -> ApiResult {
+    let response_data = SolAmountData {
+        lamports: req.lamports,
+        sol: format!("{}.{:09}", req.lamports / LAMPORTS_PER_SOL, req.lamports % LAMPORTS_PER_SOL),
+        sol_trimmed: format_ui_amount(req.lamports, 9),
+    };
+    
+    Ok(success_response(response_data))
+}
+
+fn parse_account_metas(accounts: &[AccountMetaInput]) -> Result<Vec<AccountMeta>, String> {
+    accounts
+        .iter()
+        .map(|meta| {
+            parse_pubkey(&meta.pubkey, &format!("Invalid account address {}", meta.pubkey))
+                .map(|pubkey| AccountMeta {
+                    pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+        })
+        .collect()
+}
+
+// Largest memo the SPL Memo program documents for a single-signer transaction; also reported by /limits
+const MAX_MEMO_BYTES: usize = 566;
+
+// Memo instructions carry their text as the instruction data, which the program requires to be UTF-8
+fn validate_memo(program_id: &Pubkey, data: &[u8]) -> Result<(), String> {
+    if *program_id != spl_memo::id() && *program_id != spl_memo::v1::id() {
+        return Ok(());
+    }
+    if data.len() > MAX_MEMO_BYTES {
+        return Err(format!("Memo is {} bytes; at most {} are allowed", data.len(), MAX_MEMO_BYTES));
+    }
+    std::str::from_utf8(data).map_err(|_| "Memo must be valid UTF-8".to_string())?;
+    Ok(())
+}
+
+fn parse_instruction(input: &InstructionInput) -> Result<Instruction, String> {
+    let program_id = parse_pubkey(&input.program_id, "Invalid program id")?;
+    
+    let accounts = parse_account_metas(&input.accounts)?;
+    
+    let data = STANDARD
+        .decode(&input.instruction_data)
+        .map_err(|_| "Invalid instruction data, expected base64".to_string())?;
+    validate_memo(&program_id, &data)?;
+    
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+async fn encode_instruction(req: EncodeInstructionRequest) -> ApiResult {
+    
+    let program_id = match parse_pubkey(&req.program_id, "Invalid program id") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let accounts = match parse_account_metas(&req.accounts) {
+        Ok(accounts) => accounts,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let data = match req.data_encoding.as_deref().unwrap_or("base64") {
+        "base64" => STANDARD.decode(&req.data).map_err(|_| "Invalid instruction data, expected base64"),
+        "hex" => hex::decode(&req.data).map_err(|_| "Invalid instruction data, expected hex"),
+        _ => Err("Unsupported data encoding, expected \"base64\" or \"hex\""),
+    };
+    let data = match data {
+        Ok(data) => data,
+        Err(message) => return Ok(error_response(message)),
+    };
+    if let Err(message) = validate_memo(&program_id, &data) {
+        return Ok(error_response(&message));
+    }
+    
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data,
+    };
+    
+    let response_data = InstructionData {
+        program_id: instruction.program_id.to_string(),
+        accounts: instruction_accounts(&instruction),
+        instruction_data: STANDARD.encode(&instruction.data),
+        ui_amount_string: None,
+    };
+    
+    Ok(success_response(with_blockhash_hint(response_data)))
+}
+
+fn optional_pubkey(value: COption<Pubkey>) -> Option<String> {
+    Option::from(value).map(|pubkey: Pubkey| pubkey.to_string())
+}
+
+// Names the instruction variant and lists its arguments; account addresses are not part of the data
+fn describe_token_instruction(instruction: &token_instruction::TokenInstruction) -> (&'static str, serde_json::Value) {
+    use token_instruction::TokenInstruction::*;
+    match instruction {
+        InitializeMint { decimals, mint_authority, freeze_authority } => (
+            "InitializeMint",
+            json!({
+                "decimals": decimals,
+                "mint_authority": mint_authority.to_string(),
+                "freeze_authority": optional_pubkey(*freeze_authority),
+            }),
+        ),
+        InitializeMint2 { decimals, mint_authority, freeze_authority } => (
+            "InitializeMint2",
+            json!({
+                "decimals": decimals,
+                "mint_authority": mint_authority.to_string(),
+                "freeze_authority": optional_pubkey(*freeze_authority),
+            }),
+        ),
+        InitializeAccount => ("InitializeAccount", json!({})),
+        InitializeAccount2 { owner } => ("InitializeAccount2", json!({ "owner": owner.to_string() })),
+        InitializeAccount3 { owner } => ("InitializeAccount3", json!({ "owner": owner.to_string() })),
+        InitializeMultisig { m } => ("InitializeMultisig", json!({ "m": m })),
+        InitializeMultisig2 { m } => ("InitializeMultisig2", json!({ "m": m })),
+        Transfer { amount } => ("Transfer", json!({ "amount": amount })),
+        Approve { amount } => ("Approve", json!({ "amount": amount })),
+        Revoke => ("Revoke", json!({})),
+        SetAuthority { authority_type, new_authority } => (
+            "SetAuthority",
+            json!({
+                "authority_type": format!("{:?}", authority_type),
+                "new_authority": optional_pubkey(*new_authority),
+            }),
+        ),
+        MintTo { amount } => ("MintTo", json!({ "amount": amount })),
+        Burn { amount } => ("Burn", json!({ "amount": amount })),
+        CloseAccount => ("CloseAccount", json!({})),
+        FreezeAccount => ("FreezeAccount", json!({})),
+        ThawAccount => ("ThawAccount", json!({})),
+        TransferChecked { amount, decimals } => (
+            "TransferChecked",
+            json!({ "amount": amount, "decimals": decimals, "ui_amount_string": format_ui_amount(*amount, *decimals) }),
+        ),
+        ApproveChecked { amount, decimals } => (
+            "ApproveChecked",
+            json!({ "amount": amount, "decimals": decimals, "ui_amount_string": format_ui_amount(*amount, *decimals) }),
+        ),
+        MintToChecked { amount, decimals } => (
+            "MintToChecked",
+            json!({ "amount": amount, "decimals": decimals, "ui_amount_string": format_ui_amount(*amount, *decimals) }),
+        ),
+        BurnChecked { amount, decimals } => (
+            "BurnChecked",
+            json!({ "amount": amount, "decimals": decimals, "ui_amount_string": format_ui_amount(*amount, *decimals) }),
+        ),
+        SyncNative => ("SyncNative", json!({})),
+        GetAccountDataSize => ("GetAccountDataSize", json!({})),
+        InitializeImmutableOwner => ("InitializeImmutableOwner", json!({})),
+        AmountToUiAmount { amount } => ("AmountToUiAmount", json!({ "amount": amount })),
+        UiAmountToAmount { ui_amount } => ("UiAmountToAmount", json!({ "ui_amount": ui_amount })),
+    }
+}
+
+async fn decode_token_instruction(req: DecodeTokenInstructionRequest) -> ApiResult {
+    
+    let data = match req.data_encoding.as_deref().unwrap_or("base64") {
+        "base64" => STANDARD.decode(&req.data).map_err(|_| "Invalid instruction data, expected base64"),
+        "hex" => hex::decode(&req.data).map_err(|_| "Invalid instruction data, expected hex"),
+        _ => Err("Unsupported data encoding, expected \"base64\" or \"hex\""),
+    };
+    let data = match data {
+        Ok(data) => data,
+        Err(message) => return Ok(error_response(message)),
+    };
+    
+    let instruction = match token_instruction::TokenInstruction::unpack(&data) {
+        Ok(instruction) => instruction,
+        Err(_) => return Ok(error_response("Data is not a valid SPL token instruction")),
+    };
+    
+    let (name, fields) = describe_token_instruction(&instruction);
+    let response_data = DecodedTokenInstructionData {
+        instruction: name.to_string(),
+        fields,
     };
     
     Ok(success_response(response_data))
 }
 
+// Compiling a message merges duplicate metas (keeping the strongest flags) and orders the keys
+// signers first, then writables; the header records where each group ends
+fn compiled_account_keys(message: &VersionedMessage) -> Vec<AccountInfo> {
+    let header = message.header();
+    let keys = message.static_account_keys();
+    let num_signers = header.num_required_signatures as usize;
+    let num_writable_signers = num_signers.saturating_sub(header.num_readonly_signed_accounts as usize);
+    let num_writable_unsigned = keys
+        .len()
+        .saturating_sub(num_signers)
+        .saturating_sub(header.num_readonly_unsigned_accounts as usize);
+    
+    keys.iter()
+        .enumerate()
+        .map(|(index, pubkey)| AccountInfo {
+            pubkey: pubkey.to_string(),
+            is_signer: index < num_signers,
+            is_writable: if index < num_signers {
+                index < num_writable_signers
+            } else {
+                index - num_signers < num_writable_unsigned
+            },
+        })
+        .collect()
+}
+
+// An empty or "0" blockhash leaves the field zeroed for a signing service to fill in later
+fn is_blockhash_placeholder(value: &str) -> bool {
+    let value = value.trim();
+    value.is_empty() || value == "0"
+}
+
+// Compiles the requested instructions into an unsigned transaction with placeholder signatures
+fn compile_transaction(req: &BuildTransactionRequest) -> Result<(&str, VersionedTransaction), String> {
+    
+    let fee_payer = parse_pubkey(&req.fee_payer, "Invalid fee payer address")?;
+    
+    let recent_blockhash = if is_blockhash_placeholder(&req.recent_blockhash) {
+        Hash::default()
+    } else {
+        // Surrounding whitespace is ignored, as it is for pubkeys
+        match Hash::from_str(req.recent_blockhash.trim()) {
+            Ok(hash) => hash,
+            Err(_) => return Err("Invalid recent blockhash, expected a 32-byte base58 hash or an empty placeholder".to_string()),
+        }
+    };
+    
+    if req.instructions.is_empty() {
+        return Err("At least one instruction is required".to_string());
+    }
+    
+    let mut instructions = Vec::with_capacity(req.instructions.len());
+    for (index, input) in req.instructions.iter().enumerate() {
+        match parse_instruction(input) {
+            Ok(instruction) => instructions.push(instruction),
+            Err(message) => return Err(format!("Instruction {}: {}", index, message)),
+        }
+    }
+    
+    let version = req.version.as_deref().unwrap_or("legacy");
+    let message = match version {
+        "legacy" => {
+            if !req.address_lookup_tables.is_empty() {
+                return Err("Address lookup tables require version \"0\"".to_string());
+            }
+            VersionedMessage::Legacy(Message::new_with_blockhash(
+                &instructions,
+                Some(&fee_payer),
+                &recent_blockhash,
+            ))
+        }
+        "0" => {
+            let mut lookup_tables = Vec::with_capacity(req.address_lookup_tables.len());
+            for table in &req.address_lookup_tables {
+                let key = parse_pubkey(&table.address, "Invalid lookup table address")?;
+                let addresses = table
+                    .addresses
+                    .iter()
+                    .map(|address| parse_pubkey(address, "Invalid address in lookup table"))
+                    .collect::<Result<Vec<_>, _>>()?;
+                lookup_tables.push(AddressLookupTableAccount { key, addresses });
+            }
+            
+            match v0::Message::try_compile(&fee_payer, &instructions, &lookup_tables, recent_blockhash) {
+                Ok(message) => VersionedMessage::V0(message),
+                Err(_) => return Err("Failed to compile transaction message".to_string()),
+            }
+        }
+        _ => return Err("Unsupported transaction version, expected \"legacy\" or \"0\"".to_string()),
+    };
+    
+    let required_signatures = message.header().num_required_signatures;
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default(); required_signatures as usize],
+        message,
+    };
+    
+    Ok((version, transaction))
+}
 
-async fn sign_message(req: SignMessageRequest) -> ApiResult {
+async fn build_transaction(req: BuildTransactionRequest) -> ApiResult {
+    
+    let (version, transaction) = match compile_transaction(&req) {
+        Ok(compiled) => compiled,
+        Err(message) => return Ok(error_response(&message)),
+    };
     
-    let secret_bytes = match bs58::decode(&req.secret).into_vec() {
+    let transaction_bytes = match bincode::serialize(&transaction) {
         Ok(bytes) => bytes,
-        Err(_) => return Ok(error_response("Invalid secret key format")),
+        Err(_) => return Ok(error_response("Failed to serialize transaction")),
     };
     
- 
-    let keypair = match Keypair::from_bytes(&secret_bytes) {
-        Ok(kp) => kp,
-        Err(_) => return Ok(error_response("Invalid secret key")),
+    let mut warnings = Vec::new();
+    if transaction_bytes.len() > PACKET_DATA_SIZE {
+        warnings.push(format!(
+            "Transaction is {} bytes, over the {}-byte packet limit; it will be rejected by the cluster",
+            transaction_bytes.len(),
+            PACKET_DATA_SIZE
+        ));
+    }
+    let blockhash_placeholder = is_blockhash_placeholder(&req.recent_blockhash);
+    if blockhash_placeholder {
+        warnings.push("Recent blockhash is zeroed; it must be set before the transaction is signed".to_string());
+    }
+    
+    let response_data = BuiltTransactionData {
+        version: version.to_string(),
+        transaction: STANDARD.encode(&transaction_bytes),
+        serialized_size: transaction_bytes.len(),
+        required_signatures: transaction.message.header().num_required_signatures,
+        account_keys: compiled_account_keys(&transaction.message),
+        blockhash_placeholder,
     };
     
+    Ok(success_response_with_warnings(response_data, warnings))
+}
 
-    let message_bytes = req.message.as_bytes();
-    let signature = keypair.sign_message(message_bytes);
+async fn transaction_size(req: BuildTransactionRequest) -> ApiResult {
     
-    let response_data = SignatureData {
-        signature: STANDARD.encode(signature.as_ref()),
-        public_key: keypair.pubkey().to_string(),
-        message: req.message,
+    let (_, transaction) = match compile_transaction(&req) {
+        Ok(compiled) => compiled,
+        Err(message) => return Ok(error_response(&message)),
+    };
+    
+    let serialized_size = match bincode::serialized_size(&transaction) {
+        Ok(size) => size as usize,
+        Err(_) => return Ok(error_response("Failed to serialize transaction")),
+    };
+    
+    let response_data = TransactionSizeData {
+        serialized_size,
+        max_size: PACKET_DATA_SIZE,
+        exceeds_packet_limit: serialized_size > PACKET_DATA_SIZE,
     };
     
     Ok(success_response(response_data))
 }
 
-
-async fn verify_message(req: VerifyMessageRequest) -> ApiResult {
+async fn decode_transaction(req: DecodeTransactionRequest) -> ApiResult {
     
-    let pubkey = match Pubkey::from_str(&req.pubkey) {
-        Ok(pk) => pk,
-        Err(_) => return Ok(error_response("Invalid public key")),
+    let transaction_bytes = match STANDARD.decode(&req.transaction) {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(error_response("Invalid transaction encoding, expected base64")),
     };
     
-    
-    let signature_bytes = match STANDARD.decode(&req.signature) {
-        Ok(bytes) => bytes,
-        Err(_) => return Ok(error_response("Invalid signature format")),
+    let transaction: VersionedTransaction = match bincode::deserialize(&transaction_bytes) {
+        Ok(transaction) => transaction,
+        Err(_) => return Ok(error_response("Invalid transaction")),
     };
     
+    if transaction.sanitize().is_err() {
+        return Ok(error_response("Invalid transaction"));
+    }
     
-    let signature = match Signature::try_from(signature_bytes.as_slice()) {
-        Ok(sig) => sig,
-        Err(_) => return Ok(error_response("Invalid signature")),
-    };
+    let message = &transaction.message;
+    let account_keys = message.static_account_keys();
     
+    // Accounts loaded through address lookup tables cannot be resolved offline
+    let account_label = |index: usize| match account_keys.get(index) {
+        Some(pubkey) => pubkey.to_string(),
+        None => format!("lookup_table_account_{}", index - account_keys.len()),
+    };
     
-    let message_bytes = req.message.as_bytes();
-    let is_valid = signature.verify(&pubkey.to_bytes(), message_bytes);
+    let instructions = message
+        .instructions()
+        .iter()
+        .map(|instruction| InstructionData {
+            program_id: account_label(instruction.program_id_index as usize),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|&index| AccountInfo {
+                    pubkey: account_label(index as usize),
+                    is_signer: message.is_signer(index as usize),
+                    is_writable: message.is_maybe_writable(index as usize),
+                })
+                .collect(),
+            instruction_data: STANDARD.encode(&instruction.data),
+            ui_amount_string: None,
+        })
+        .collect();
     
-    let response_data = VerifyData {
-        valid: is_valid,
-        message: req.message,
-        pubkey: req.pubkey,
+    let response_data = DecodedTransactionData {
+        version: match message {
+            VersionedMessage::Legacy(_) => "legacy".to_string(),
+            VersionedMessage::V0(_) => "0".to_string(),
+        },
+        fee_payer: account_label(0),
+        recent_blockhash: message.recent_blockhash().to_string(),
+        signatures: transaction
+            .signatures
+            .iter()
+            .map(|signature| signature.to_string())
+            .collect(),
+        instructions,
     };
     
     Ok(success_response(response_data))
 }
 
-
-async fn send_sol(req: SendSolRequest) -> ApiResult {
-   
-    let from = match Pubkey::from_str(&req.from) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid from address")),
+async fn required_signers(req: RequiredSignersRequest) -> ApiResult {
+    
+    let message_bytes = match STANDARD.decode(&req.message) {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(error_response("Invalid message encoding, expected base64")),
     };
     
-    let to = match Pubkey::from_str(&req.to) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid to address")),
+    let message: VersionedMessage = match bincode::deserialize(&message_bytes) {
+        Ok(message) => message,
+        Err(_) => return Ok(error_response("Invalid message")),
     };
     
-
-    if req.lamports == 0 {
-        return Ok(error_response("Lamports amount must be greater than 0"));
+    if message.sanitize().is_err() {
+        return Ok(error_response("Invalid message"));
     }
     
-  
-    let instruction = system_instruction::transfer(&from, &to, req.lamports);
+    let pubkey = match req.pubkey.as_deref().map(|pubkey| parse_pubkey(pubkey, "Invalid public key")).transpose() {
+        Ok(pubkey) => pubkey,
+        Err(message) => return Ok(error_response(&message)),
+    };
     
-    let response_data = SolTransferData {
-        program_id: solana_sdk::system_program::id().to_string(),
-        accounts: vec![req.from, req.to],
-        instruction_data: STANDARD.encode(&instruction.data),
+    // Signers are always the first num_required_signatures static keys; lookup tables never hold signers
+    let signers = &message.static_account_keys()[..message.header().num_required_signatures as usize];
+    
+    let response_data = RequiredSignersData {
+        required_signers: signers.iter().map(|signer| signer.to_string()).collect(),
+        is_required_signer: pubkey.map(|pubkey| signers.contains(&pubkey)),
     };
     
     Ok(success_response(response_data))
 }
 
 
-async fn send_token(req: SendTokenRequest) -> ApiResult {
-   
-    let destination = match Pubkey::from_str(&req.destination) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid destination address")),
-    };
+
+// Adds one party's signature in its signer slot, leaving signatures from the other parties untouched
+async fn partial_sign_transaction(req: PartialSignRequest) -> ApiResult {
     
-    let mint = match Pubkey::from_str(&req.mint) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid mint address")),
+    let transaction_bytes = match STANDARD.decode(&req.transaction) {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(error_response("Invalid transaction encoding, expected base64")),
     };
     
-    let owner = match Pubkey::from_str(&req.owner) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(error_response("Invalid owner address")),
+    let mut transaction: VersionedTransaction = match bincode::deserialize(&transaction_bytes) {
+        Ok(transaction) => transaction,
+        Err(_) => return Ok(error_response("Invalid transaction")),
     };
     
-    
-    if req.amount == 0 {
-        return Ok(error_response("Amount must be greater than 0"));
+    if transaction.sanitize().is_err() {
+        return Ok(error_response("Invalid transaction"));
     }
     
-  
-    let source_ata = spl_associated_token_account::get_associated_token_address(&owner, &mint);
-    let dest_ata = spl_associated_token_account::get_associated_token_address(&destination, &mint);
+    let secret_bytes = match decode_secret(&req.secret) {
+        Ok(bytes) => bytes,
+        Err(message) => return Ok(error_response(&message)),
+    };
     
+    let keypair = match Keypair::from_bytes(&secret_bytes) {
+        Ok(kp) => kp,
+        Err(_) => return Ok(error_response("Invalid secret key")),
+    };
     
-    let instruction = match token_instruction::transfer(
-        &spl_token::id(),
-        &source_ata,
-        &dest_ata,
-        &owner,
-        &[],
-        req.amount,
-    ) {
-        Ok(instruction) => instruction,
-        Err(_) => return Ok(error_response("Failed to create transfer instruction")),
+    let signatures_required = transaction.message.header().num_required_signatures as usize;
+    let Some(signer_index) = transaction.message.static_account_keys()[..signatures_required]
+        .iter()
+        .position(|signer| *signer == keypair.pubkey())
+    else {
+        return Ok(error_response(&format!(
+            "{} is not a required signer of this transaction",
+            keypair.pubkey()
+        )));
     };
     
-    let response_data = TokenTransferData {
-        program_id: spl_token::id().to_string(),
-        accounts: vec![
-            TokenAccountInfo {
-                pubkey: source_ata.to_string(),
-                is_signer: false,
-            },
-            TokenAccountInfo {
-                pubkey: dest_ata.to_string(),
-                is_signer: false,
-            },
-            TokenAccountInfo {
-                pubkey: owner.to_string(),
-                is_signer: true,
-            },
-        ],
-        instruction_data: STANDARD.encode(&instruction.data),
+    transaction.signatures[signer_index] = keypair.sign_message(&transaction.message.serialize());
+    
+    let signed_bytes = match bincode::serialize(&transaction) {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(error_response("Failed to serialize transaction")),
+    };
+    
+    let signatures_present = transaction
+        .signatures
+        .iter()
+        .filter(|signature| **signature != Signature::default())
+        .count();
+    
+    let response_data = PartiallySignedTransactionData {
+        transaction: STANDARD.encode(&signed_bytes),
+        signer: keypair.pubkey().to_string(),
+        signatures_present,
+        signatures_required,
+        fully_signed: signatures_present == signatures_required,
     };
     
     Ok(success_response(response_data))
 }
 
-
+// Checks one signature against the message bytes for the signer in the same slot
+async fn verify_transaction_signature(req: VerifyTransactionSignatureRequest) -> ApiResult {
+    
+    let transaction_bytes = match STANDARD.decode(&req.transaction) {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(error_response("Invalid transaction encoding, expected base64")),
+    };
+    
+    let transaction: VersionedTransaction = match bincode::deserialize(&transaction_bytes) {
+        Ok(transaction) => transaction,
+        Err(_) => return Ok(error_response("Invalid transaction")),
+    };
+    
+    if transaction.sanitize().is_err() {
+        return Ok(error_response("Invalid transaction"));
+    }
+    
+    let signatures_required = transaction.message.header().num_required_signatures as usize;
+    if req.signature_index >= signatures_required {
+        return Ok(error_response(&format!(
+            "signature_index {} is out of range; the transaction has {} required signatures",
+            req.signature_index,
+            signatures_required
+        )));
+    }
+    
+    let signer = transaction.message.static_account_keys()[req.signature_index];
+    let signature = transaction.signatures[req.signature_index];
+    
+    let response_data = TransactionSignatureCheckData {
+        valid: signature.verify(signer.as_ref(), &transaction.message.serialize()),
+        signer_pubkey: signer.to_string(),
+        signature: signature.to_string(),
+    };
+    
+    Ok(success_response(response_data))
+}
 
 #[tokio::main]
 async fn main() {
@@ -422,57 +5079,793 @@ async fn main() {
   
     let cors = warp::cors()
         .allow_any_origin()
-        .allow_headers(vec!["content-type", "authorization"])
-        .allow_methods(vec!["GET", "POST", "OPTIONS"]);
+        .allow_headers(vec!["content-type", "authorization", "idempotency-key", "x-echo"])
+        .allow_methods(vec!["GET", "POST", "OPTIONS"])
+        .expose_headers(vec!["x-warnings"]);
     
    
-    let keypair_route = warp::path("keypair")
+    let request_timeout = env::var("REQUEST_TIMEOUT_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs);
+    REQUEST_TIMEOUT.get_or_init(|| request_timeout);
+    
+    let max_body = env::var("MAX_BODY_BYTES")
+        .ok()
+        .and_then(|bytes| bytes.parse::<u64>().ok())
+        .filter(|bytes| *bytes > 0)
+        .unwrap_or(DEFAULT_MAX_BODY_BYTES);
+    MAX_BODY_BYTES.get_or_init(|| max_body);
+    
+    let max_batch = env::var("MAX_BATCH_OPERATIONS")
+        .ok()
+        .and_then(|count| count.parse::<usize>().ok())
+        .filter(|count| *count > 0)
+        .unwrap_or(DEFAULT_MAX_BATCH_OPERATIONS);
+    MAX_BATCH_OPERATIONS.get_or_init(|| max_batch);
+    
+    let derivation_cache_capacity = env::var("DERIVATION_CACHE_CAPACITY")
+        .ok()
+        .and_then(|capacity| capacity.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_DERIVATION_CACHE_CAPACITY);
+    DERIVATION_CACHE.get_or_init(|| DerivationCache::new(derivation_cache_capacity));
+    
+    let max_concurrent_requests = env::var("MAX_CONCURRENT_REQUESTS")
+        .ok()
+        .and_then(|permits| permits.parse::<usize>().ok())
+        .filter(|permits| *permits > 0);
+    CONCURRENCY_LIMIT.get_or_init(|| max_concurrent_requests.map(Semaphore::new));
+    
+    let max_cpu_bound_tasks = env::var("MAX_CPU_BOUND_TASKS")
+        .ok()
+        .and_then(|permits| permits.parse::<usize>().ok())
+        .filter(|permits| *permits > 0)
+        .unwrap_or_else(default_cpu_bound_tasks);
+    CPU_BOUND_LIMIT.get_or_init(|| Arc::new(Semaphore::new(max_cpu_bound_tasks)));
+    
+    let default_freeze_authority = match env::var("DEFAULT_FREEZE_AUTHORITY").ok().as_deref() {
+        None | Some("") | Some("none") => DefaultFreezeAuthority::None,
+        Some("mint_authority") => DefaultFreezeAuthority::MintAuthority,
+        Some(value) => match Pubkey::from_str(value) {
+            Ok(pubkey) => DefaultFreezeAuthority::Pubkey(pubkey),
+            Err(_) => {
+                eprintln!("❌ DEFAULT_FREEZE_AUTHORITY must be \"none\", \"mint_authority\" or a pubkey");
+                std::process::exit(1);
+            }
+        },
+    };
+    DEFAULT_FREEZE_AUTHORITY.get_or_init(|| default_freeze_authority);
+    
+    PROBLEM_JSON_ERRORS.get_or_init(|| env::var("ERROR_FORMAT").is_ok_and(|format| format == "problem+json"));
+    
+    let log_bodies = env::var("LOG_BODIES").is_ok_and(|value| value == "true" || value == "1");
+    if log_bodies {
+        println!("🐛 Logging request and response bodies with secrets redacted");
+    }
+    LOG_BODIES.get_or_init(|| log_bodies);
+    
+    let log_sample_rate = match env::var("LOG_SAMPLE_RATE").ok() {
+        None => 1.0,
+        Some(value) => match value.parse::<f64>() {
+            Ok(rate) if (0.0..=1.0).contains(&rate) => rate,
+            _ => {
+                eprintln!("❌ LOG_SAMPLE_RATE must be a number between 0.0 and 1.0");
+                std::process::exit(1);
+            }
+        },
+    };
+    if log_sample_rate < 1.0 {
+        println!("🐛 Logging {}% of successful requests; errors are always logged", log_sample_rate * 100.0);
+    }
+    LOG_SAMPLE_RATE.get_or_init(|| log_sample_rate);
+    
+    let base_path_segments: Vec<String> = env::var("BASE_PATH")
+        .unwrap_or_default()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect();
+    
+    let disabled_endpoints: Vec<Vec<String>> = env::var("DISABLED_ENDPOINTS")
+        .unwrap_or_default()
+        .split(',')
+        .flat_map(disabled_endpoint_prefixes)
+        .collect();
+    for segments in &disabled_endpoints {
+        println!("🚫 Disabled endpoints under /{}", segments.join("/"));
+    }
+    
+    let master_seed = env::var("KEYPAIR_MASTER_SEED").ok().filter(|seed| !seed.is_empty());
+    
+    let keypair_route = warp::path!("keypair")
+        .and(warp::post())
+        .and(warp::query::<KeypairQuery>())
+        .and(warp::any().map(move || master_seed.clone()))
+        .and_then(|query, master_seed| run_handler(generate_keypair(query, master_seed)));
+    
+    let keypair_stream_route = warp::path!("keypair" / "stream")
+        .and(warp::post())
+        .and(warp::query::<KeypairStreamQuery>())
+        .and_then(|query| run_handler_with_permit(|permit| stream_keypairs(query, permit)));
+    
+    let derive_keypair_route = warp::path!("keypair" / "derive")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(derive_keypair(req))));
+    
+    let keypair_from_keyfile_route = warp::path!("keypair" / "from-file-format")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(keypair_from_keyfile(req))));
+    
+    let inspect_keypair_route = warp::path!("keypair" / "inspect")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(inspect_keypair(req))));
+    
+    let verify_pair_route = warp::path!("keypair" / "verify-pair")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(verify_keypair_pair(req))));
+    
+    let sign_challenge_route = warp::path!("keypair" / "sign-challenge")
         .and(warp::post())
-        .and_then(generate_keypair);
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(sign_challenge(req))));
+    
+    let off_curve_route = warp::path!("pubkey" / "off-curve")
+        .and(warp::post())
+        .and_then(|| run_handler(off_curve_pubkey()));
+    
+    let normalize_pubkey_route = warp::path!("pubkey" / "normalize")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(normalize_pubkey(req))));
+    
+    let pubkey_encodings_route = warp::path!("pubkey" / "encodings")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(pubkey_encodings(req))));
+    
+    let derive_address_route = warp::path!("address" / "derive")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(derive_address(req))));
     
     let create_token_route = warp::path!("token" / "create")
         .and(warp::post())
-        .and(warp::body::json())
-        .and_then(create_token);
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(create_token(req, estimate))));
     
     let mint_token_route = warp::path!("token" / "mint")
         .and(warp::post())
-        .and(warp::body::json())
-        .and_then(mint_token);
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(mint_token(req, estimate))));
+    
+    let mint_token_batch_route = warp::path!("token" / "mint" / "batch")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(mint_token_batch(req, estimate))));
+    
+    let mint_token_checked_route = warp::path!("token" / "mint-checked")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(mint_token_checked(req, estimate))));
+    
+    let burn_token_checked_route = warp::path!("token" / "burn-checked")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(burn_token_checked(req, estimate))));
+    
+    let close_with_transfer_route = warp::path!("token" / "close-with-transfer")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(close_with_transfer(req, estimate))));
+    
+    let init_multisig_route = warp::path!("token" / "init-multisig")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(init_multisig(req, estimate))));
+    
+    let sync_native_route = warp::path!("token" / "sync-native")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(sync_native(req, estimate))));
+    
+    let wrap_sol_route = warp::path!("token" / "wrap-sol")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(wrap_sol(req, estimate))));
     
     let sign_message_route = warp::path!("message" / "sign")
         .and(warp::post())
-        .and(warp::body::json())
-        .and_then(sign_message);
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(sign_message(req))));
+    
+    let sign_siws_route = warp::path!("message" / "sign-siws")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(sign_siws_message(req))));
+    
+    let sign_multi_route = warp::path!("sign" / "multi")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(sign_message_multi(req))));
     
     let verify_message_route = warp::path!("message" / "verify")
         .and(warp::post())
-        .and(warp::body::json())
-        .and_then(verify_message);
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(verify_message(req))));
+    
+    let ed25519_verify_route = warp::path!("ed25519" / "build-verify-ix")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(build_ed25519_verify_instruction(req))));
+    
+    let secp256k1_verify_route = warp::path!("secp256k1" / "build-verify-ix")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(build_secp256k1_verify_instruction(req))));
     
     let send_sol_route = warp::path!("send" / "sol")
         .and(warp::post())
-        .and(warp::body::json())
-        .and_then(send_sol);
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(send_sol(req, estimate))));
+    
+    let send_sol_multi_route = warp::path!("send" / "sol" / "multi")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(send_sol_multi(req, estimate))));
+    
+    let create_account_route = warp::path!("account" / "create")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(create_account(req, estimate))));
+    
+    let allocate_assign_route = warp::path!("account" / "allocate-assign")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(allocate_assign(req, estimate))));
+    
+    let withdraw_nonce_route = warp::path!("nonce" / "withdraw")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(withdraw_nonce(req, estimate))));
     
     let send_token_route = warp::path!("send" / "token")
         .and(warp::post())
-        .and(warp::body::json())
-        .and_then(send_token);
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(send_token(req, estimate))));
+    
+    let stats = Arc::new(ServerStats {
+        started_at: Instant::now(),
+        requests_served: AtomicU64::new(0),
+    });
+    
+    let health_stats = stats.clone();
+    let health_base_path = format!("/{}", base_path_segments.join("/"));
+    let health_route = warp::path!("health")
+        .and(warp::get())
+        .and(warp::any().map(move || health_stats.clone()))
+        .and(warp::any().map(move || health_base_path.clone()))
+        .and_then(|stats, base_path| run_handler(health(stats, base_path)));
+    
+    let send_token_with_ata_route = warp::path!("send" / "token" / "with-ata")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(send_token_with_ata(req, estimate))));
+    
+    let program_ids_route = warp::path!("program-ids")
+        .and(warp::get())
+        .and_then(|| run_handler(program_ids()));
+    
+    let version_route = warp::path!("version")
+        .and(warp::get())
+        .and_then(|| run_handler(version()));
+    
+    let limits_route = warp::path!("limits")
+        .and(warp::get())
+        .and_then(|| run_handler(limits()));
+    
+    let examples_route = warp::path!("examples")
+        .and(warp::get())
+        .and_then(|| run_handler(examples()));
+    
+    let batch_route = warp::path!("batch")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::query::<ComputeEstimateQuery>())
+        .and_then(|req, context, estimate| attach_request_context(context, run_handler(batch(req, estimate))));
+    
+    let decode_token_instruction_route = warp::path!("token" / "decode-instruction")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(decode_token_instruction(req))));
+    
+    let mint_size_route = warp::path!("token" / "mint-size")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(mint_size(req))));
+    
+    let token_setup_cost_route = warp::path!("token" / "setup-cost")
+        .and(warp::post())
+        .and_then(|| run_handler(token_setup_cost()));
+    
+    let lamports_to_sol_route = warp::path!("convert" / "lamports-to-sol")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(lamports_to_sol(req))));
+    
+    let cost_breakdown_route = warp::path!("cost" / "breakdown")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(cost_breakdown(req))));
+    
+    let rent_minimum_route = warp::path!("rent" / "minimum")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(rent_minimum(req))));
+    
+    let encode_instruction_route = warp::path!("instruction" / "encode")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(encode_instruction(req))));
+    
+    let build_transaction_route = warp::path!("transaction" / "build")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(build_transaction(req))));
+    
+    let transaction_size_route = warp::path!("transaction" / "size")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(transaction_size(req))));
+    
+    let required_signers_route = warp::path!("transaction" / "required-signers")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(required_signers(req))));
+    
+    let partial_sign_route = warp::path!("transaction" / "partial-sign")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(partial_sign_transaction(req))));
+    
+    let verify_transaction_signature_route = warp::path!("transaction" / "verify-signature")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(verify_transaction_signature(req))));
+    
+    let decode_transaction_route = warp::path!("transaction" / "decode")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(|req, context| attach_request_context(context, run_handler(decode_transaction(req))));
     
   
-    let routes = keypair_route
+    // Boxed in two groups so the full filter type stays within the compiler's recursion limit
+    let instruction_routes = keypair_route
+        .or(keypair_stream_route)
+        .or(derive_keypair_route)
+        .or(keypair_from_keyfile_route)
+        .or(inspect_keypair_route)
+        .or(verify_pair_route)
+        .or(sign_challenge_route)
+        .or(off_curve_route)
+        .or(normalize_pubkey_route)
+        .or(pubkey_encodings_route)
+        .or(derive_address_route)
         .or(create_token_route)
         .or(mint_token_route)
+        .or(mint_token_batch_route)
+        .or(mint_token_checked_route)
+        .or(burn_token_checked_route)
+        .or(close_with_transfer_route)
+        .or(init_multisig_route)
+        .or(sync_native_route)
+        .or(wrap_sol_route)
         .or(sign_message_route)
+        .or(sign_siws_route)
+        .or(sign_multi_route)
         .or(verify_message_route)
+        .or(ed25519_verify_route)
+        .or(secp256k1_verify_route)
         .or(send_sol_route)
+        .or(send_sol_multi_route)
+        .or(create_account_route)
+        .or(allocate_assign_route)
+        .or(withdraw_nonce_route)
         .or(send_token_route)
+        .or(send_token_with_ata_route)
+        .boxed();
+    
+    let api_routes = instruction_routes
+        .or(health_route)
+        .or(program_ids_route)
+        .or(version_route)
+        .or(limits_route)
+        .or(examples_route)
+        .or(batch_route)
+        .or(cost_breakdown_route)
+        .or(rent_minimum_route)
+        .or(lamports_to_sol_route)
+        .or(token_setup_cost_route)
+        .or(mint_size_route)
+        .or(decode_token_instruction_route)
+        .or(encode_instruction_route)
+        .or(build_transaction_route)
+        .or(transaction_size_route)
+        .or(required_signers_route)
+        .or(partial_sign_route)
+        .or(verify_transaction_signature_route)
+        .or(decode_transaction_route)
+        .boxed();
+    
+    let count_requests = warp::any()
+        .map(move || {
+            stats.requests_served.fetch_add(1, Ordering::Relaxed);
+        })
+        .untuple_one();
+    
+    let idempotency_cache: IdempotencyCache = Arc::new(Mutex::new(HashMap::new()));
+    let replay_cache = idempotency_cache.clone();
+    let replayed_response = idempotency_key(base_path_segments.len())
+        .and(warp::any().map(move || replay_cache.clone()))
+        .and_then(cached_idempotent_response)
+        .and(warp::body::stream())
+        .and_then(replay_idempotent_response);
+    let fresh_response = idempotency_key(base_path_segments.len())
+        .and(warp::any().map(move || idempotency_cache.clone()))
+        .and(
+            base_path_filter(&base_path_segments)
+                .and(endpoint_guard(base_path_segments.len(), Arc::new(disabled_endpoints)))
+                .and(api_routes)
+                .recover(handle_rejection),
+        )
+        .and_then(store_idempotent_response);
+    
+    let routes = count_requests
+        .and(response_options())
+        .and(
+            warp::any()
+                .map(Instant::now)
+                .and(warp::method())
+                .and(warp::path::full())
+                .and(unsupported_data_encoding().or(replayed_response).unify().or(fresh_response).unify())
+                .and_then(log_response),
+        )
+        .and_then(negotiate_response)
         .with(cors);
     
- 
+    let tls_files = match (env::var("TLS_CERT_PATH").ok(), env::var("TLS_KEY_PATH").ok()) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = std::fs::read(&cert_path).unwrap_or_else(|err| {
+                eprintln!("❌ Failed to read TLS certificate {}: {}", cert_path, err);
+                std::process::exit(1);
+            });
+            let key = std::fs::read(&key_path).unwrap_or_else(|err| {
+                eprintln!("❌ Failed to read TLS key {}: {}", key_path, err);
+                std::process::exit(1);
+            });
+            Some((cert, key))
+        }
+        (Some(_), None) | (None, Some(_)) => {
+            eprintln!("❌ TLS_CERT_PATH and TLS_KEY_PATH must be set together");
+            std::process::exit(1);
+        }
+        (None, None) => None,
+    };
     
-    warp::serve(routes)
-        .run(([0, 0, 0, 0], port))
-        .await;
+    match tls_files {
+        Some((cert, key)) => {
+            println!("🔒 Serving HTTPS on port {}", port);
+            warp::serve(routes)
+                .tls()
+                .cert(cert)
+                .key(key)
+                .run(([0, 0, 0, 0], port))
+                .await;
+        }
+        None => {
+            warp::serve(routes)
+                .run(([0, 0, 0, 0], port))
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    async fn reply_body(result: ApiResult) -> serde_json::Value {
+        let Ok(reply) = result else {
+            panic!("handler rejected the request");
+        };
+        let body = warp::hyper::body::to_bytes(warp::Reply::into_response(reply).into_body())
+            .await
+            .expect("response body is readable");
+        serde_json::from_slice(&body).expect("response body is JSON")
+    }
+    
+    #[tokio::test]
+    async fn keypair_secret_round_trips_through_from_bytes() {
+        let query: KeypairQuery = serde_json::from_value(json!({})).expect("every query field is optional");
+        
+        let body = reply_body(generate_keypair(query, None).await).await;
+        let secret = body["data"]["secret"].as_str().expect("secret is returned by default");
+        let bytes = bs58::decode(secret).into_vec().expect("secret is base58");
+        let keypair = Keypair::from_bytes(&bytes).expect("secret is a 64-byte keypair");
+        assert_eq!(body["data"]["pubkey"], keypair.pubkey().to_string());
+    }
+    
+    #[tokio::test]
+    async fn send_sol_accounts_match_system_transfer() {
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let req = SendSolRequest {
+            from: from.to_string(),
+            to: to.to_string(),
+            lamports: 1_000_000,
+        };
+        
+        let body = reply_body(send_sol(req, ComputeEstimateQuery { estimate_compute_units: false }).await).await;
+        let expected = system_instruction::transfer(&from, &to, 1_000_000);
+        let flags: Vec<(Pubkey, bool, bool)> = expected
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_signer, meta.is_writable))
+            .collect();
+        assert_eq!(flags, vec![(from, true, true), (to, false, true)]);
+        assert_eq!(body["data"]["accounts"], json!([from.to_string(), to.to_string()]));
+        assert_eq!(body["data"]["instruction_data"], STANDARD.encode(&expected.data));
+    }
+    
+    #[tokio::test]
+    async fn cost_breakdown_charges_one_signature_for_send_sol() {
+        let req = CostBreakdownRequest {
+            operations: vec![BatchOperation::SendSol(SendSolRequest {
+                from: Pubkey::new_unique().to_string(),
+                to: Pubkey::new_unique().to_string(),
+                lamports: 1_000_000,
+            })],
+            fee_payer: None,
+        };
+        
+        let body = reply_body(cost_breakdown(req).await).await;
+        assert_eq!(body["data"]["signers"].as_array().map(Vec::len), Some(1));
+        assert_eq!(body["data"]["signature_fee_lamports"], 5000);
+        assert_eq!(body["data"]["total_lamports"], 5000);
+    }
+    
+    #[test]
+    fn sign_group_disables_every_secret_taking_route() {
+        let prefixes: Vec<String> = ["keypair", "sign"]
+            .into_iter()
+            .flat_map(disabled_endpoint_prefixes)
+            .map(|segments| segments.join("/"))
+            .collect();
+        for path in ["keypair", "sign", "message/sign", "message/sign-siws", "transaction/partial-sign"] {
+            assert!(prefixes.iter().any(|prefix| prefix == path), "{} is not disabled", path);
+        }
+    }
+    
+    #[test]
+    fn optional_lamports_accept_numeric_strings() {
+        let parse = |body: serde_json::Value| serde_json::from_value::<CreateAccountRequest>(body).map(|req| req.lamports);
+        let base = json!({
+            "payer": Pubkey::new_unique().to_string(),
+            "new_account": Pubkey::new_unique().to_string(),
+            "owner": Pubkey::new_unique().to_string(),
+            "space": 0,
+        });
+        let with_lamports = |lamports: serde_json::Value| {
+            let mut body = base.clone();
+            body["lamports"] = lamports;
+            body
+        };
+        
+        assert_eq!(parse(base.clone()).ok(), Some(None));
+        assert_eq!(parse(with_lamports(json!(890880))).ok(), Some(Some(890880)));
+        assert_eq!(parse(with_lamports(json!("18446744073709551615"))).ok(), Some(Some(u64::MAX)));
+        assert!(parse(with_lamports(json!("12abc"))).is_err());
+    }
+    
+    #[tokio::test]
+    async fn idempotency_key_reused_with_another_body_is_refused() {
+        let cache: IdempotencyCache = Arc::new(Mutex::new(HashMap::new()));
+        let replay_cache = cache.clone();
+        let replayed_response = idempotency_key(0)
+            .and(warp::any().map(move || replay_cache.clone()))
+            .and_then(cached_idempotent_response)
+            .and(warp::body::stream())
+            .and_then(replay_idempotent_response);
+        let fresh_response = idempotency_key(0)
+            .and(warp::any().map(move || cache.clone()))
+            .and(warp::path!("send" / "sol").and(json_body()).and_then(|req, context| {
+                attach_request_context(context, send_sol(req, ComputeEstimateQuery { estimate_compute_units: false }))
+            }))
+            .and_then(store_idempotent_response);
+        let routes = replayed_response.or(fresh_response).unify();
+        
+        let from = Pubkey::new_unique().to_string();
+        let to = Pubkey::new_unique().to_string();
+        let send = |body: serde_json::Value| {
+            warp::test::request()
+                .method("POST")
+                .path("/send/sol")
+                .header("idempotency-key", "retry-1")
+                .json(&body)
+                .reply(&routes)
+        };
+        
+        let first = send(json!({ "from": from, "to": to, "lamports": 1_000_000 })).await;
+        assert_eq!(first.status(), 200);
+        
+        let reordered = send(json!({ "lamports": 1_000_000, "to": to, "from": from })).await;
+        assert_eq!(reordered.status(), 200);
+        assert_eq!(reordered.headers().get("idempotent-replayed").map(|value| value.as_bytes()), Some(&b"true"[..]));
+        assert_eq!(reordered.body(), first.body());
+        
+        let changed = send(json!({ "from": from, "to": to, "lamports": 2_000_000 })).await;
+        assert_eq!(changed.status(), 422);
+        assert!(changed.headers().get("idempotent-replayed").is_none());
+    }
+    
+    #[tokio::test]
+    async fn batch_stops_once_its_request_is_cancelled() {
+        let operation = || {
+            BatchOperation::SendSol(SendSolRequest {
+                from: Pubkey::new_unique().to_string(),
+                to: Pubkey::new_unique().to_string(),
+                lamports: 1_000_000,
+            })
+        };
+        let req = BatchRequest {
+            operations: vec![operation(), operation()],
+        };
+        let expired = Cancellation {
+            deadline: Some(Instant::now()),
+            dropped: Arc::new(AtomicBool::new(false)),
+        };
+        
+        let result = CANCELLATION
+            .scope(expired, batch(req, ComputeEstimateQuery { estimate_compute_units: false }))
+            .await;
+        let Ok(reply) = result else {
+            panic!("handler rejected the request");
+        };
+        assert_eq!(warp::Reply::into_response(reply).status(), warp::http::StatusCode::GATEWAY_TIMEOUT);
+    }
+    
+    #[test]
+    fn derivation_cache_evicts_the_least_recently_used_address() {
+        let cache = DerivationCache::new(2);
+        let mint = Pubkey::new_unique();
+        let [a, b, c] = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let derive = |owner: &Pubkey| cache.associated_token_address(owner, &mint, &spl_token::id());
+        
+        let first = derive(&a);
+        derive(&b);
+        assert_eq!(derive(&a), first);
+        derive(&c);
+        derive(&a);
+        derive(&b);
+        
+        assert_eq!(first, spl_associated_token_account::get_associated_token_address(&a, &mint));
+        assert_eq!(cache.hits.load(Ordering::Relaxed), 2);
+        assert_eq!(cache.misses.load(Ordering::Relaxed), 4);
+    }
+    
+    #[tokio::test]
+    async fn unknown_data_encoding_is_refused_before_the_handler() {
+        let refused = warp::test::request()
+            .path("/send/sol?data_encodings=hex,bogus")
+            .filter(&unsupported_data_encoding())
+            .await
+            .expect("an unknown encoding is answered by the filter");
+        assert_eq!(refused.status(), warp::http::StatusCode::BAD_REQUEST);
+        
+        let passed = warp::test::request()
+            .path("/send/sol?data_encodings=hex,base58")
+            .filter(&unsupported_data_encoding())
+            .await;
+        assert!(passed.is_err(), "known encodings fall through to the route");
+    }
+    
+    #[tokio::test]
+    async fn custom_token_program_addresses_the_built_instruction() {
+        let fork = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let req: MintCheckedRequest = serde_json::from_value(json!({
+            "mint": mint.to_string(),
+            "destination": destination.to_string(),
+            "authority": authority.to_string(),
+            "amount": 5,
+            "decimals": 6,
+            "program_id": fork.to_string(),
+        }))
+        .expect("valid mint-checked body");
+        
+        let body = reply_body(mint_token_checked(req, ComputeEstimateQuery { estimate_compute_units: false }).await).await;
+        let expected = token_instruction::mint_to_checked(&spl_token::id(), &mint, &destination, &authority, &[], 5, 6)
+            .expect("canonical instruction builds");
+        assert_eq!(body["data"]["program_id"], fork.to_string());
+        assert_eq!(body["data"]["instruction_data"], STANDARD.encode(&expected.data));
+    }
+    
+    #[tokio::test]
+    async fn bare_responses_carry_warnings_in_headers() {
+        let options = warp::test::request()
+            .path("/?envelope=false")
+            .filter(&response_options())
+            .await
+            .expect("query parses");
+        let reply = success_response_with_warnings(json!({ "valid": true }), vec!["first".to_string(), "second".to_string()]);
+        
+        let response = negotiate_response(options, reply).await.expect("response is negotiated");
+        let warnings: Vec<_> = response.headers().get_all("x-warnings").iter().map(|value| value.as_bytes().to_vec()).collect();
+        assert_eq!(warnings, vec![b"first".to_vec(), b"second".to_vec()]);
+        let body = warp::hyper::body::to_bytes(response.into_body()).await.expect("response body is readable");
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&body).ok(), Some(json!({ "valid": true })));
+    }
+    
+    #[tokio::test]
+    async fn memo_limit_matches_what_limits_reports() {
+        let limits = reply_body(limits().await).await;
+        let max_memo_bytes = limits["data"]["max_memo_bytes"].as_u64().expect("limit is reported") as usize;
+        let encode = |memo: Vec<u8>| {
+            let req: EncodeInstructionRequest = serde_json::from_value(json!({
+                "program_id": spl_memo::id().to_string(),
+                "accounts": [],
+                "data": STANDARD.encode(memo),
+            }))
+            .expect("valid encode body");
+            encode_instruction(req)
+        };
+        
+        let at_limit = reply_body(encode(vec![b'a'; max_memo_bytes]).await).await;
+        assert_eq!(at_limit["success"], true);
+        let over_limit = reply_body(encode(vec![b'a'; max_memo_bytes + 1]).await).await;
+        assert_eq!(over_limit["success"], false);
+    }
+    
+    #[test]
+    fn recent_blockhash_is_trimmed_before_parsing() {
+        let blockhash = Hash::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&fee_payer, &Pubkey::new_unique(), 1);
+        let req: BuildTransactionRequest = serde_json::from_value(json!({
+            "fee_payer": fee_payer.to_string(),
+            "recent_blockhash": format!(" {}\n", blockhash),
+            "instructions": [{
+                "program_id": transfer.program_id.to_string(),
+                "accounts": instruction_accounts(&transfer),
+                "instruction_data": STANDARD.encode(&transfer.data),
+            }],
+        }))
+        .expect("valid build body");
+        
+        let (_, transaction) = compile_transaction(&req).expect("padded blockhash parses");
+        assert_eq!(*transaction.message.recent_blockhash(), blockhash);
+    }
 }